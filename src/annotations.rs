@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use chess_logic::Board;
+
+use crate::{
+    notation::{parse_square, position_key, square_name},
+    rules::Pos,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnotationColor {
+    Blue,
+    Green,
+    Red,
+    Yellow,
+}
+
+impl AnnotationColor {
+    /// Letter used for the color in PGN `[%cal]`/`[%csl]` commands.
+    pub fn letter(&self) -> char {
+        match self {
+            Self::Blue => 'B',
+            Self::Green => 'G',
+            Self::Red => 'R',
+            Self::Yellow => 'Y',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter {
+            'B' => Self::Blue,
+            'G' => Self::Green,
+            'R' => Self::Red,
+            'Y' => Self::Yellow,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arrow {
+    pub color: AnnotationColor,
+    pub from: Pos,
+    pub to: Pos,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SquareMark {
+    pub color: AnnotationColor,
+    pub pos: Pos,
+}
+
+/// Arrows and marked squares drawn on a single position.
+#[derive(Clone, Debug, Default)]
+pub struct Annotations {
+    pub arrows: Vec<Arrow>,
+    pub squares: Vec<SquareMark>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty() && self.squares.is_empty()
+    }
+
+    /// Adds the arrow, or removes it if the exact same arrow already exists.
+    /// An arrow between the same squares in another color is replaced.
+    pub fn toggle_arrow(&mut self, arrow: Arrow) {
+        let existing = self
+            .arrows
+            .iter()
+            .position(|a| a.from == arrow.from && a.to == arrow.to);
+
+        if let Some(index) = existing {
+            if self.arrows.remove(index).color == arrow.color {
+                return;
+            }
+        }
+
+        self.arrows.push(arrow);
+    }
+
+    /// Same as [`Self::toggle_arrow`], but for square marks.
+    pub fn toggle_square(&mut self, mark: SquareMark) {
+        let existing = self.squares.iter().position(|m| m.pos == mark.pos);
+
+        if let Some(index) = existing {
+            if self.squares.remove(index).color == mark.color {
+                return;
+            }
+        }
+
+        self.squares.push(mark);
+    }
+
+    /// Formats the annotations as PGN comment commands, e.g.
+    /// `[%csl Gd4][%cal Ge2e4,Rg8f6]`.
+    pub fn to_pgn_commands(&self, board: &Board) -> String {
+        let mut commands = String::new();
+
+        if !self.squares.is_empty() {
+            let squares: Vec<String> = self
+                .squares
+                .iter()
+                .map(|m| format!("{}{}", m.color.letter(), square_name(board, m.pos)))
+                .collect();
+
+            commands.push_str(&format!("[%csl {}]", squares.join(",")));
+        }
+
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self
+                .arrows
+                .iter()
+                .map(|a| {
+                    format!(
+                        "{}{}{}",
+                        a.color.letter(),
+                        square_name(board, a.from),
+                        square_name(board, a.to)
+                    )
+                })
+                .collect();
+
+            commands.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+
+        commands
+    }
+
+    /// Reads the `[%csl]` and `[%cal]` commands of a PGN comment. Everything
+    /// else in the comment is ignored.
+    pub fn from_pgn_commands(board: &Board, comment: &str) -> Result<Self, String> {
+        let mut annotations = Self::default();
+
+        for (command, args) in iter_commands(comment) {
+            for arg in args.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+                let invalid = || format!("invalid '{}' argument '{}'", command, arg);
+
+                let color = arg
+                    .chars()
+                    .next()
+                    .and_then(AnnotationColor::from_letter)
+                    .ok_or_else(invalid)?;
+
+                match command {
+                    "csl" if arg.len() == 3 => {
+                        let pos = parse_square(board, &arg[1..3]).ok_or_else(invalid)?;
+
+                        annotations.squares.push(SquareMark { color, pos });
+                    }
+                    "cal" if arg.len() == 5 => {
+                        let from = parse_square(board, &arg[1..3]).ok_or_else(invalid)?;
+                        let to = parse_square(board, &arg[3..5]).ok_or_else(invalid)?;
+
+                        annotations.arrows.push(Arrow { color, from, to });
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+
+        Ok(annotations)
+    }
+}
+
+/// Yields `(command, arguments)` of every `[%csl ...]` or `[%cal ...]` in the
+/// comment.
+fn iter_commands(comment: &str) -> impl Iterator<Item = (&str, &str)> {
+    comment.split("[%").skip(1).filter_map(|part| {
+        let (body, _) = part.split_once(']')?;
+        let (command, args) = body.split_once(' ')?;
+
+        match command {
+            "csl" | "cal" => Some((command, args)),
+            _ => None,
+        }
+    })
+}
+
+/// Keeps the annotations of every position that was annotated, so they
+/// reappear when the position is shown again.
+#[derive(Clone, Debug, Default)]
+pub struct AnnotationStore {
+    by_position: HashMap<String, Annotations>,
+}
+
+impl AnnotationStore {
+    pub fn get(&self, board: &Board) -> Option<&Annotations> {
+        self.by_position.get(&position_key(board))
+    }
+
+    pub fn get_mut(&mut self, board: &Board) -> &mut Annotations {
+        self.by_position.entry(position_key(board)).or_default()
+    }
+
+    pub fn insert(&mut self, key: String, annotations: Annotations) {
+        self.by_position.insert(key, annotations);
+    }
+
+    /// Iterates the annotated positions by their [`position_key`].
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Annotations)> {
+        self.by_position.iter().filter(|(_, a)| !a.is_empty())
+    }
+}
@@ -0,0 +1,357 @@
+//! Speaks UCI over stdin and stdout, so the rules and the search of this
+//! crate can be used from chess GUIs and tournament managers, e.g.
+//! `cutechess-cli -engine cmd=chess_uci -engine cmd=stockfish -each proto=uci`.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use chess::{
+    engine::{self, SearchInfo, SearchLimits},
+    notation, rules,
+};
+use chess_logic::{Board, Color, Player};
+
+/// Depth used when `go` does not limit it, high enough to never be reached.
+const MAX_DEPTH: u32 = 64;
+/// Most lines reported at once with the `MultiPV` option.
+const MAX_MULTI_PV: usize = 16;
+/// Kept off the remaining time, so the move arrives before the flag falls.
+const TIME_MARGIN: Duration = Duration::from_millis(50);
+
+/// Search running on its own thread, so `stop` can be read meanwhile.
+struct RunningSearch {
+    handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl RunningSearch {
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+fn main() {
+    let (mut board, mut to_move) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+    let mut level = engine::MAX_LEVEL;
+    let mut multi_pv = 1;
+    let mut running: Option<RunningSearch> = None;
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+        match command {
+            "uci" => {
+                println!("id name Chess {}", env!("CARGO_PKG_VERSION"));
+                println!("id author Chess contributors");
+                println!(
+                    "option name Skill Level type spin default {} min {} max {}",
+                    engine::MAX_LEVEL,
+                    engine::MIN_LEVEL,
+                    engine::MAX_LEVEL
+                );
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                );
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "setoption" => {
+                if let Some(value) = args.strip_prefix("name Skill Level value ") {
+                    level = value.trim().parse().unwrap_or(level);
+                } else if let Some(value) = args.strip_prefix("name MultiPV value ") {
+                    multi_pv = value
+                        .trim()
+                        .parse()
+                        .unwrap_or(multi_pv)
+                        .clamp(1, MAX_MULTI_PV);
+                }
+            }
+            "ucinewgame" | "position" => {
+                if let Some(search) = running.take() {
+                    search.stop();
+                }
+
+                if command == "position" {
+                    match parse_position(args) {
+                        Some(position) => (board, to_move) = position,
+                        None => println!("info string invalid position '{}'", args),
+                    }
+                }
+            }
+            "go" => {
+                if let Some(search) = running.take() {
+                    search.stop();
+                }
+
+                running = Some(start_search(&board, &to_move, args, level, multi_pv));
+            }
+            "stop" => {
+                if let Some(search) = running.take() {
+                    search.stop();
+                }
+            }
+            "quit" => break,
+            _ => (),
+        }
+    }
+
+    if let Some(search) = running.take() {
+        search.stop();
+    }
+}
+
+/// Parses the arguments of `position`, like `startpos moves e2e4 e7e5` or
+/// `fen <fen> moves e2e4`.
+fn parse_position(args: &str) -> Option<(Board, Player)> {
+    let (position, moves) = match args.split_once(" moves ") {
+        Some((position, moves)) => (position, moves),
+        None => (args.trim_end_matches(" moves"), ""),
+    };
+
+    let (mut board, mut to_move) = match position.trim() {
+        "startpos" => notation::parse_fen(notation::STARTING_FEN)?,
+        position => notation::parse_fen(position.strip_prefix("fen ")?)?,
+    };
+
+    for uci in moves.split_whitespace() {
+        let mv = notation::parse_uci(&board, uci)?;
+
+        board = rules::apply_move(&board, &mv)?;
+        to_move = rules::opponent_of(&to_move);
+    }
+
+    Some((board, to_move))
+}
+
+/// Starts searching the position with the limits of the `go` arguments. The
+/// best move is printed once the search is done, or, for `go infinite`, once
+/// it is stopped.
+fn start_search(
+    board: &Board,
+    to_move: &Player,
+    args: &str,
+    level: u8,
+    multi_pv: usize,
+) -> RunningSearch {
+    let (mut limits, infinite) = parse_go(board, to_move, args, level);
+    let board = board.clone();
+    let to_move = to_move.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+
+    limits.multi_pv = multi_pv;
+
+    let handle = thread::spawn(move || {
+        let result = engine::search(&board, &to_move, &limits, &thread_stop, |lines| {
+            for (index, info) in lines.iter().enumerate() {
+                println!("{}", format_info(&board, info, index + 1));
+            }
+        });
+
+        // The GUI decides when an infinite search ends.
+        while infinite && !thread_stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        match result.as_ref().and_then(|result| result.best_move()) {
+            Some(mv) => println!("bestmove {}", notation::format_uci(&board, mv)),
+            None => println!("bestmove 0000"),
+        }
+    });
+
+    RunningSearch { handle, stop }
+}
+
+/// Limits of the `go` arguments, and whether the search is infinite. Without
+/// a depth or time limit, the time of the skill level is used.
+fn parse_go(board: &Board, to_move: &Player, args: &str, level: u8) -> (SearchLimits, bool) {
+    let mut limits = SearchLimits::for_level(level);
+    let mut words = args.split_whitespace();
+    let mut depth = None;
+    let mut move_time = None;
+    let mut infinite = false;
+    let (mut time, mut increment, mut moves_to_go) = (None, 0, 30);
+    let (time_key, increment_key) = match board.get_color_of_player(to_move) {
+        Color::White => ("wtime", "winc"),
+        Color::Black => ("btime", "binc"),
+    };
+
+    while let Some(word) = words.next() {
+        let mut value = || words.next().and_then(|value| value.parse::<i64>().ok());
+
+        match word {
+            "depth" => depth = value(),
+            "movetime" => move_time = value(),
+            "movestogo" => moves_to_go = value().unwrap_or(moves_to_go).max(1),
+            "infinite" => infinite = true,
+            key if key == time_key => time = value(),
+            key if key == increment_key => increment = value().unwrap_or(0),
+            _ => (),
+        }
+    }
+
+    let budget = match (move_time, time) {
+        (Some(move_time), _) => Some(move_time),
+        // Spread the remaining time over the moves still to be made.
+        (None, Some(time)) => Some((time / moves_to_go + increment / 2).min(time)),
+        (None, None) => None,
+    };
+
+    if depth.is_some() || budget.is_some() {
+        limits.time = budget
+            .map(|millis| Duration::from_millis(millis.max(0) as u64).saturating_sub(TIME_MARGIN));
+    }
+
+    limits.depth = match depth {
+        Some(depth) => depth.clamp(1, MAX_DEPTH as i64) as u32,
+        None if level == engine::MAX_LEVEL => MAX_DEPTH,
+        None => limits.depth,
+    };
+
+    if infinite {
+        limits.time = None;
+        limits.depth = MAX_DEPTH;
+    }
+
+    (limits, infinite)
+}
+
+/// Formats a line of an iteration as an `info` line, `multipv` being its
+/// rank among the lines.
+fn format_info(board: &Board, info: &SearchInfo, multipv: usize) -> String {
+    let score = match engine::mate_in(info.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+
+    let mut pv = Vec::new();
+    let mut position = board.clone();
+
+    for mv in &info.pv {
+        pv.push(notation::format_uci(&position, mv));
+
+        position = match rules::apply_move(&position, mv) {
+            Some(after) => after,
+            None => break,
+        };
+    }
+
+    format!(
+        "info depth {} multipv {} score {} nodes {} pv {}",
+        info.depth,
+        multipv,
+        score,
+        info.nodes,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fen_of(args: &str) -> Option<String> {
+        parse_position(args).map(|(board, to_move)| notation::format_fen(&board, &to_move))
+    }
+
+    fn start() -> (Board, Player) {
+        notation::parse_fen(notation::STARTING_FEN).unwrap()
+    }
+
+    #[test]
+    fn parses_positions() {
+        assert_eq!(fen_of("startpos").as_deref(), Some(notation::STARTING_FEN));
+        assert_eq!(
+            fen_of("startpos moves e2e4 e7e5").as_deref(),
+            Some("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 1")
+        );
+        assert_eq!(
+            fen_of("fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1 moves e1g1").as_deref(),
+            Some("4k3/8/8/8/8/8/8/5RK1 b - - 0 1")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        assert!(fen_of("").is_none());
+        assert!(fen_of("8/8/8/8/8/8/8/8 w - - 0 1").is_none());
+        assert!(fen_of("fen 8/8/8 w - - 0 1").is_none());
+        assert!(fen_of("startpos moves e2e5").is_none());
+    }
+
+    #[test]
+    fn go_without_limits_uses_the_level() {
+        let (board, to_move) = start();
+        let (limits, infinite) = parse_go(&board, &to_move, "", 3);
+        let level = SearchLimits::for_level(3);
+
+        assert!(!infinite);
+        assert_eq!(limits.depth, level.depth);
+        assert_eq!(limits.time, level.time);
+    }
+
+    #[test]
+    fn go_with_depth_or_move_time() {
+        let (board, to_move) = start();
+        let (limits, _) = parse_go(&board, &to_move, "depth 3", engine::MAX_LEVEL);
+
+        assert_eq!(limits.depth, 3);
+        assert_eq!(limits.time, None);
+
+        let (limits, _) = parse_go(&board, &to_move, "depth 0", engine::MAX_LEVEL);
+
+        assert_eq!(limits.depth, 1);
+
+        let (limits, _) = parse_go(&board, &to_move, "movetime 1000", engine::MAX_LEVEL);
+
+        assert_eq!(limits.depth, MAX_DEPTH);
+        assert_eq!(limits.time, Some(Duration::from_millis(950)));
+    }
+
+    #[test]
+    fn go_spreads_the_clock_of_the_player_to_move() {
+        let (board, white) = start();
+        let black = rules::opponent_of(&white);
+        let args = "wtime 60000 btime 1000 winc 2000 binc 0";
+
+        let (limits, _) = parse_go(&board, &white, args, engine::MAX_LEVEL);
+
+        assert_eq!(limits.time, Some(Duration::from_millis(2_950)));
+
+        let (limits, _) = parse_go(&board, &black, args, engine::MAX_LEVEL);
+
+        assert_eq!(limits.time, Some(Duration::ZERO));
+
+        let (limits, _) = parse_go(
+            &board,
+            &white,
+            "wtime 60000 movestogo 10",
+            engine::MAX_LEVEL,
+        );
+
+        assert_eq!(limits.time, Some(Duration::from_millis(5_950)));
+    }
+
+    #[test]
+    fn go_infinite() {
+        let (board, to_move) = start();
+        let (limits, infinite) = parse_go(&board, &to_move, "infinite", 1);
+
+        assert!(infinite);
+        assert_eq!(limits.depth, MAX_DEPTH);
+        assert_eq!(limits.time, None);
+    }
+}
@@ -0,0 +1,406 @@
+//! Speaks the Chess Engine Communication Protocol over stdin and stdout, so
+//! the rules and the search of this crate can be used from xboard, WinBoard
+//! and other CECP interfaces, e.g. `xboard -fcp chess_xboard`.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chess::{
+    engine::{self, SearchInfo, SearchLimits},
+    notation, rules,
+};
+use chess_logic::{Board, Player};
+
+/// Depth used when `sd` did not limit it, high enough to never be reached.
+const MAX_DEPTH: u32 = 64;
+/// Kept off the remaining time, so the move arrives before the flag falls.
+const TIME_MARGIN: Duration = Duration::from_millis(50);
+/// Moves the remaining time is spread over if the time control does not say.
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+/// The game as the interface told it.
+struct Position {
+    board: Board,
+    /// Positions before the moves, for `undo` and `remove`.
+    history: Vec<(Board, Player)>,
+    to_move: Player,
+}
+
+impl Position {
+    fn new(board: Board, to_move: Player) -> Self {
+        Self {
+            board,
+            history: Vec::new(),
+            to_move,
+        }
+    }
+
+    fn play(&mut self, uci: &str) -> bool {
+        let after = notation::parse_move(&self.board, &self.to_move, uci)
+            .and_then(|mv| rules::apply_move(&self.board, &mv));
+
+        match after {
+            Some(after) => {
+                let before = std::mem::replace(&mut self.board, after);
+                let to_move = rules::opponent_of(&self.to_move);
+
+                self.history
+                    .push((before, std::mem::replace(&mut self.to_move, to_move)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some((board, to_move)) = self.history.pop() {
+            self.board = board;
+            self.to_move = to_move;
+        }
+    }
+}
+
+/// Time control of the `level`, `st` and `sd` commands.
+struct TimeControl {
+    depth: Option<u32>,
+    increment: Duration,
+    /// Moves per session of a classical time control, `0` for sudden death.
+    moves_per_session: u64,
+    /// Time per move set by `st`, overrides the rest.
+    move_time: Option<Duration>,
+    /// Remaining time of the engine, as told by `time`.
+    remaining: Option<Duration>,
+}
+
+impl TimeControl {
+    /// Time for the next move, after `moves_played` full moves.
+    fn budget(&self, moves_played: u64) -> Option<Duration> {
+        if let Some(move_time) = self.move_time {
+            return Some(move_time);
+        }
+
+        let remaining = self.remaining?;
+        let moves_to_go = match self.moves_per_session {
+            0 => DEFAULT_MOVES_TO_GO,
+            per_session => per_session - moves_played % per_session,
+        };
+        let budget = remaining / moves_to_go as u32 + self.increment / 2;
+
+        Some(budget.min(remaining).saturating_sub(TIME_MARGIN))
+    }
+}
+
+/// Search running on its own thread, so commands can be read meanwhile.
+struct RunningSearch {
+    handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+}
+
+struct Engine {
+    /// Player the engine plays, set by `go`.
+    engine_player: Option<Player>,
+    /// The engine only moves when told to with `go`.
+    force: bool,
+    /// Incremented by every command that makes a running search pointless,
+    /// so it ends without playing its move.
+    generation: Arc<AtomicU64>,
+    position: Arc<Mutex<Position>>,
+    post: bool,
+    running: Option<RunningSearch>,
+    time_control: TimeControl,
+}
+
+impl Engine {
+    /// Ends the running search without playing its move.
+    fn cancel(&mut self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(search) = self.running.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.handle.join();
+        }
+    }
+
+    /// Lets the running search end early and play the best move found.
+    fn move_now(&mut self) {
+        if let Some(search) = &self.running {
+            search.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Starts thinking if it is the engine's turn.
+    fn think_if_on_move(&mut self) {
+        let position = self.position.lock().unwrap();
+        let on_move = !self.force && self.engine_player.as_ref() == Some(&position.to_move);
+
+        drop(position);
+
+        if on_move {
+            self.start_search(false);
+        }
+    }
+
+    /// Searches the current position. A search for a move plays it once it
+    /// is done, an analysis runs until it is cancelled.
+    fn start_search(&mut self, analyse: bool) {
+        self.cancel();
+
+        let position = self.position.lock().unwrap();
+        let board = position.board.clone();
+        let to_move = position.to_move.clone();
+        let moves_played = position.history.len() as u64 / 2;
+
+        drop(position);
+
+        let mut limits = SearchLimits::for_level(engine::MAX_LEVEL);
+
+        if analyse {
+            limits.depth = MAX_DEPTH;
+            limits.time = None;
+        } else {
+            limits.depth = self.time_control.depth.unwrap_or(MAX_DEPTH);
+            limits.time = self.time_control.budget(moves_played).or(limits.time);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let generation = self.generation.clone();
+        let started_generation = generation.load(Ordering::SeqCst);
+        let shared_position = self.position.clone();
+        let post = self.post || analyse;
+
+        let handle = thread::spawn(move || {
+            let started = Instant::now();
+            let result = engine::search(&board, &to_move, &limits, &thread_stop, |lines| {
+                if post {
+                    println!("{}", format_thinking(&board, &to_move, &lines[0], started));
+                }
+            });
+
+            // An analysis runs until the interface ends it.
+            while analyse && !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            let mut position = shared_position.lock().unwrap();
+
+            if analyse || generation.load(Ordering::SeqCst) != started_generation {
+                return;
+            }
+
+            match result.as_ref().and_then(|result| result.best_move()) {
+                Some(mv) => {
+                    let uci = notation::format_uci(&board, mv);
+
+                    position.play(&uci);
+                    println!("move {}", uci);
+                }
+                None if rules::is_in_check(&board, &to_move) => println!("resign"),
+                None => println!("1/2-1/2 {{Stalemate}}"),
+            }
+        });
+
+        self.running = Some(RunningSearch { handle, stop });
+    }
+}
+
+fn main() {
+    let (board, to_move) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+    let mut engine = Engine {
+        engine_player: Some(Player::Opponent),
+        force: false,
+        generation: Arc::new(AtomicU64::new(0)),
+        position: Arc::new(Mutex::new(Position::new(board, to_move))),
+        post: false,
+        running: None,
+        time_control: TimeControl {
+            depth: None,
+            increment: Duration::ZERO,
+            moves_per_session: 0,
+            move_time: None,
+            remaining: None,
+        },
+    };
+    let mut analysing = false;
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+        match command {
+            "protover" => println!(
+                "feature myname=\"Chess {}\" setboard=1 usermove=1 ping=1 analyze=1 colors=0 \
+                 sigint=0 sigterm=0 reuse=1 done=1",
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                engine.cancel();
+
+                let (board, to_move) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+
+                *engine.position.lock().unwrap() = Position::new(board, to_move);
+                engine.force = false;
+                engine.engine_player = Some(Player::Opponent);
+                engine.time_control.depth = None;
+                analysing = false;
+            }
+            "setboard" => {
+                engine.cancel();
+
+                match notation::parse_fen(args) {
+                    Some((board, to_move)) => {
+                        *engine.position.lock().unwrap() = Position::new(board, to_move);
+                    }
+                    None => println!("tellusererror Illegal position"),
+                }
+            }
+            "force" => {
+                engine.cancel();
+                engine.force = true;
+            }
+            "go" => {
+                engine.force = false;
+                engine.engine_player = Some(engine.position.lock().unwrap().to_move.clone());
+                engine.think_if_on_move();
+            }
+            "playother" => {
+                engine.force = false;
+                engine.engine_player =
+                    Some(rules::opponent_of(&engine.position.lock().unwrap().to_move));
+            }
+            "usermove" => {
+                engine.cancel();
+
+                if !engine.position.lock().unwrap().play(args) {
+                    println!("Illegal move: {}", args);
+                } else if analysing {
+                    engine.start_search(true);
+                } else {
+                    engine.think_if_on_move();
+                }
+            }
+            "undo" | "remove" => {
+                engine.cancel();
+
+                let mut position = engine.position.lock().unwrap();
+
+                position.undo();
+
+                if command == "remove" {
+                    position.undo();
+                }
+
+                drop(position);
+
+                if analysing {
+                    engine.start_search(true);
+                }
+            }
+            "?" => engine.move_now(),
+            "analyze" => {
+                analysing = true;
+                engine.start_search(true);
+            }
+            "exit" => {
+                analysing = false;
+                engine.cancel();
+            }
+            "level" => {
+                if let Some(time_control) = parse_level(args) {
+                    engine.time_control = TimeControl {
+                        depth: engine.time_control.depth,
+                        remaining: engine.time_control.remaining,
+                        ..time_control
+                    };
+                }
+            }
+            "st" => {
+                engine.time_control.move_time =
+                    args.trim().parse().ok().map(Duration::from_secs_f64);
+            }
+            "sd" => engine.time_control.depth = args.trim().parse().ok(),
+            // Centiseconds.
+            "time" => {
+                engine.time_control.remaining = args
+                    .trim()
+                    .parse()
+                    .ok()
+                    .map(|centis: u64| Duration::from_millis(centis * 10));
+            }
+            "ping" => println!("pong {}", args),
+            "post" => engine.post = true,
+            "nopost" => engine.post = false,
+            "result" => {
+                engine.cancel();
+                engine.force = true;
+            }
+            "quit" => break,
+            _ => (),
+        }
+    }
+
+    engine.cancel();
+}
+
+/// Parses the arguments of `level`, like `40 5 0` or `0 2:30 1`: the moves per
+/// session, the base time in minutes (optionally with seconds) and the
+/// increment in seconds.
+fn parse_level(args: &str) -> Option<TimeControl> {
+    let mut words = args.split_whitespace();
+    let moves_per_session = words.next()?.parse().ok()?;
+    // Not needed, as the interface sends the remaining time before each move.
+    let _base = words.next()?;
+    let increment: f64 = words.next()?.parse().ok()?;
+
+    Some(TimeControl {
+        depth: None,
+        increment: Duration::from_secs_f64(increment.max(0.0)),
+        moves_per_session,
+        move_time: None,
+        remaining: None,
+    })
+}
+
+/// Formats the result of an iteration as thinking output: depth, score in
+/// centipawns, time in centiseconds, nodes and the best line in SAN. Mates
+/// in N moves score `100000 + N`, as the protocol asks.
+fn format_thinking(board: &Board, to_move: &Player, info: &SearchInfo, started: Instant) -> String {
+    let mut pv = Vec::new();
+    let mut position = board.clone();
+    let mut player = to_move.clone();
+
+    for mv in &info.pv {
+        pv.push(notation::format_san(&position, &player, mv));
+
+        position = match rules::apply_move(&position, mv) {
+            Some(after) => after,
+            None => break,
+        };
+        player = rules::opponent_of(&player);
+    }
+
+    let score = match engine::mate_in(info.score) {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => info.score,
+    };
+
+    format!(
+        "{} {} {} {} {}",
+        info.depth,
+        score,
+        started.elapsed().as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    )
+}
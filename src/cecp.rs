@@ -0,0 +1,312 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    engine_process::EngineProcess,
+    notation,
+    uci::{GoLimits, UciEvent, UciInfo, UciScore, HANDSHAKE_TIMEOUT},
+};
+
+/// Engines that don't know `protover 2` send no features, the protocol asks
+/// to give up waiting for them after two seconds.
+const FEATURE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Features the client understands. Everything else is rejected, so the
+/// engine falls back to the defaults.
+const ACCEPTED_FEATURES: [&str; 11] = [
+    "analyze",
+    "colors",
+    "done",
+    "myname",
+    "ping",
+    "playother",
+    "reuse",
+    "setboard",
+    "sigint",
+    "sigterm",
+    "usermove",
+];
+
+/// External engine speaking the Chess Engine Communication Protocol, as used
+/// by xboard and WinBoard.
+///
+/// The interface matches [`crate::uci::UciEngine`], so both kinds of engines
+/// can be used the same way. Thinking output is reported as
+/// [`UciEvent::Info`] and moves as [`UciEvent::BestMove`].
+pub struct CecpEngine {
+    analysing: bool,
+    name: String,
+    process: EngineProcess,
+    searching: bool,
+    /// `analyze` is supported.
+    supports_analyze: bool,
+    /// `setboard` is supported, otherwise only games from the standard
+    /// starting position can be set up.
+    supports_setboard: bool,
+    /// An analysis was stopped. It ends without a move, which is reported
+    /// by the next [`Self::poll`].
+    stopped_analysis: bool,
+    /// Moves are sent as `usermove e2e4` instead of `e2e4`.
+    usermove: bool,
+}
+
+impl CecpEngine {
+    /// Starts the engine and waits until it announced its features.
+    pub fn start(program: &str) -> Result<Self, Box<dyn Error>> {
+        let mut engine = Self {
+            analysing: false,
+            name: program.to_owned(),
+            process: EngineProcess::start(program)?,
+            searching: false,
+            supports_analyze: true,
+            supports_setboard: false,
+            stopped_analysis: false,
+            usermove: false,
+        };
+
+        engine.process.send("xboard")?;
+        engine.process.send("protover 2")?;
+
+        let mut deadline = Instant::now() + FEATURE_TIMEOUT;
+
+        loop {
+            let line = match engine.process.read_line(deadline) {
+                Ok(line) => line,
+                Err(_) if !engine.process.has_exited() => break,
+                Err(err) => return Err(err),
+            };
+
+            let features = match line.strip_prefix("feature ") {
+                Some(features) => parse_features(features),
+                None => continue,
+            };
+
+            let mut done = false;
+
+            for (name, value) in features {
+                match (name.as_str(), value.as_str()) {
+                    ("analyze", value) => engine.supports_analyze = value == "1",
+                    ("done", "0") => deadline = Instant::now() + HANDSHAKE_TIMEOUT,
+                    ("done", _) => done = true,
+                    ("myname", value) => engine.name = value.to_owned(),
+                    ("setboard", value) => engine.supports_setboard = value == "1",
+                    ("usermove", value) => engine.usermove = value == "1",
+                    _ => (),
+                }
+
+                let answer = match ACCEPTED_FEATURES.contains(&name.as_str()) {
+                    true => "accepted",
+                    false => "rejected",
+                };
+
+                engine.process.send(&format!("{} {}", answer, name))?;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        // Thinking output is needed for the analysis, pondering would make
+        // the engine think on the user's time.
+        for command in ["new", "force", "post", "easy"] {
+            engine.process.send(command)?;
+        }
+
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    /// Whether the engine closed its output, usually because it crashed.
+    pub fn has_exited(&self) -> bool {
+        self.process.has_exited()
+    }
+
+    /// Sets the position as the starting position with the moves, in UCI
+    /// notation, played from it. The engine is left in force mode, so it
+    /// does not move until [`Self::go`].
+    pub fn set_position(&mut self, fen: &str, moves: &[String]) -> Result<(), Box<dyn Error>> {
+        self.process.send("new")?;
+        self.process.send("force")?;
+
+        if fen != notation::STARTING_FEN {
+            if !self.supports_setboard {
+                return Err("engine can't set up positions (no setboard)".into());
+            }
+
+            self.process.send(&format!("setboard {}", fen))?;
+        }
+
+        for mv in moves {
+            match self.usermove {
+                true => self.process.send(&format!("usermove {}", mv))?,
+                false => self.process.send(mv)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lets the engine play the side to move, or analyse the position for
+    /// [`GoLimits::Infinite`].
+    pub fn go(&mut self, limits: &GoLimits) -> Result<(), Box<dyn Error>> {
+        match limits {
+            GoLimits::Infinite if !self.supports_analyze => {
+                return Err("engine can't analyse (no analyze)".into());
+            }
+            GoLimits::Infinite => {
+                self.process.send("analyze")?;
+                self.analysing = true;
+            }
+            GoLimits::Depth(depth) => {
+                self.process.send(&format!("sd {}", depth))?;
+                self.process.send("go")?;
+            }
+            GoLimits::MoveTime(time) => {
+                // Only whole seconds are understood.
+                let seconds = time.as_secs_f64().ceil().max(1.0);
+
+                self.process.send(&format!("st {}", seconds))?;
+                self.process.send("go")?;
+            }
+        }
+
+        self.searching = true;
+
+        Ok(())
+    }
+
+    /// Asks the engine to move now, or ends the analysis.
+    pub fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.analysing {
+            self.process.send("exit")?;
+            self.analysing = false;
+            self.searching = false;
+            self.stopped_analysis = true;
+        } else if self.searching {
+            self.process.send("?")?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the output of the engine without waiting for more. Has to be
+    /// called regularly while searching, e.g. every frame.
+    pub fn poll(&mut self) -> Vec<UciEvent> {
+        let mut events = Vec::new();
+
+        if self.stopped_analysis {
+            self.stopped_analysis = false;
+            events.push(UciEvent::BestMove {
+                mv: None,
+                ponder: None,
+            });
+        }
+
+        while let Some(line) = self.process.try_read_line() {
+            if let Some(info) = parse_thinking(&line) {
+                events.push(UciEvent::Info(info));
+            } else if let Some(event) = parse_move(&line) {
+                self.searching = false;
+                events.push(event);
+            }
+        }
+
+        if self.process.has_exited() {
+            self.searching = false;
+        }
+
+        events
+    }
+}
+
+impl Drop for CecpEngine {
+    /// The process quits when it is dropped, the analysis has to end before.
+    fn drop(&mut self) {
+        if self.analysing {
+            let _ = self.process.send("exit");
+        }
+    }
+}
+
+/// Parses the `name=value` pairs of a `feature` command. Values can be
+/// quoted to contain spaces.
+pub fn parse_features(features: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = features.trim();
+
+    while let Some((name, value)) = rest.split_once('=') {
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(' ').unwrap_or((value, "")),
+        };
+
+        parsed.push((name.trim().to_owned(), value.to_owned()));
+        rest = next.trim();
+    }
+
+    parsed
+}
+
+/// Parses thinking output like `9 156 1084 48000 Nf3 Nc6 Nc3 Nf6`, which
+/// holds the depth, the score in centipawns, the time in centiseconds, the
+/// nodes and the best line. Move numbers in the line are left out, mate
+/// scores are turned into [`UciScore::Mate`].
+pub fn parse_thinking(line: &str) -> Option<UciInfo> {
+    let mut words = line.split_whitespace();
+    let depth = words
+        .next()?
+        .trim_end_matches(&['.', '&'][..])
+        .parse()
+        .ok()?;
+    let score = parse_thinking_score(words.next()?.parse().ok()?);
+    let _time: u64 = words.next()?.parse().ok()?;
+    let nodes = words.next()?.parse().ok()?;
+    let pv = words
+        .filter(|word| !word.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        .map(str::to_owned)
+        .collect();
+
+    Some(UciInfo {
+        depth: Some(depth),
+        multipv: None,
+        nodes: Some(nodes),
+        pv,
+        score: Some(score),
+    })
+}
+
+/// Mate scores have no fixed format in the protocol. The spec asks for
+/// `100000 + N` when mating in N moves, older engines send `32000 - N` with
+/// N the plies to mate. Both are negated when getting mated.
+fn parse_thinking_score(score: i32) -> UciScore {
+    let sign = score.signum();
+
+    match score.abs() {
+        abs if abs >= 100_000 => UciScore::Mate(sign * (abs - 100_000)),
+        abs if (31_000..=32_000).contains(&abs) => UciScore::Mate(sign * (32_000 - abs + 1) / 2),
+        _ => UciScore::Centipawns(score),
+    }
+}
+
+/// Parses `move e2e4`, or the end of the game announced by the engine, like
+/// `resign` or `0-1 {Black mates}`, which ends the search without a move.
+pub fn parse_move(line: &str) -> Option<UciEvent> {
+    let mut words = line.split_whitespace();
+
+    let mv = match words.next()? {
+        "move" => Some(words.next()?.to_owned()),
+        "resign" | "1-0" | "0-1" | "1/2-1/2" => None,
+        _ => return None,
+    };
+
+    Some(UciEvent::BestMove { mv, ponder: None })
+}
@@ -1,39 +1,634 @@
 use std::sync::{Arc, Mutex};
 
 use eframe::egui::{self, Widget};
+use linked_hash_map::LinkedHashMap;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
+use crate::{deserialize_game, pgn, serialize_game, Game};
+
+use analysis_widget::AnalysisLine;
+use assistant_widget::Assistant;
+use board_widget::BoardWidget;
+use computer_widget::ComputerOpponent;
+#[cfg(not(target_arch = "wasm32"))]
+use engine_widget::ExternalEngine;
+use move_list_widget::MoveListAction;
+use preferences::{Preferences, ACTIVE_TAB_KEY, GAMES_KEY, PREFERENCES_KEY};
+use report_widget::GameReviewer;
+
+mod analysis_widget;
+mod assistant_widget;
 mod board_widget;
+mod clock_widget;
+mod computer_widget;
+mod embedded_bytes_loader;
+#[cfg(not(target_arch = "wasm32"))]
+mod engine_widget;
+mod material_widget;
+mod move_list_widget;
+mod piece_animation;
+mod piece_textures;
 mod piece_widget;
+mod position_editor;
+mod preferences;
 mod promote_widget;
+mod report_widget;
+mod sound;
+mod theme;
+
+pub use embedded_bytes_loader::EmbeddedBytesLoader;
+pub use piece_textures::PieceTextures;
+
+/// Environment variable pointing to a directory whose SVGs replace the
+/// embedded pieces of the same name.
+const PIECE_OVERRIDE_DIR_VAR: &str = "CHESS_PIECE_DIR";
+
+/// Environment variable naming a file the game of the first tab is written
+/// to after every move, in addition to the eframe storage. Games in further
+/// tabs are saved next to it, with the number of the tab appended.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_PATH_VAR: &str = "CHESS_SAVE_PATH";
+
+/// A game open in its own tab.
+struct GameTab {
+    assistant: Assistant,
+    board: BoardWidget,
+    computer: ComputerOpponent,
+    #[cfg(not(target_arch = "wasm32"))]
+    engine: ExternalEngine,
+    name: String,
+    review: GameReviewer,
+}
+
+impl GameTab {
+    /// Lines of the engine analysing the shown position, the external engine
+    /// taking precedence over the built-in one.
+    fn analysis_lines(&self) -> &[AnalysisLine] {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let lines = self.engine.analysis_lines();
+
+            if !lines.is_empty() {
+                return lines;
+            }
+        }
+
+        self.computer.analysis_lines()
+    }
+}
 
 pub struct ChessClient {
-    board: board_widget::BoardWidget,
-    dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>,
+    active_tab: usize,
+    /// Used for the names and save files of new tabs, so they never clash
+    /// with those of a closed tab.
+    next_tab_number: usize,
+    piece_textures: Arc<Mutex<PieceTextures>>,
+    /// Piece set typed into the settings, applied once it is entered.
+    piece_set_input: String,
+    preferences: Preferences,
+    preferences_open: bool,
+    tabs: Vec<GameTab>,
+    time_control_input: String,
 }
 
 impl ChessClient {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        cc.egui_ctx.set_visuals(egui::Visuals::default());
+        let preferences = cc
+            .storage
+            .and_then(|storage| storage.get_string(PREFERENCES_KEY))
+            .and_then(|content| Preferences::deserialize(&content).ok())
+            .unwrap_or_default();
+
+        let bytes_loader = match std::env::var(PIECE_OVERRIDE_DIR_VAR) {
+            Ok(override_dir) => EmbeddedBytesLoader::with_override_dir(override_dir),
+            Err(_) => EmbeddedBytesLoader::new(),
+        };
 
         let dynamic_texture_manager =
             Arc::new(Mutex::new(egui_extras::DynamicTextureManager::new(
                 cc.egui_ctx.tex_manager(),
-                Box::new(egui_extras::dynamic_texture_manager::bytes_loader::FsBytesLoader),
+                Box::new(bytes_loader),
             )));
 
-        Self {
-            board: board_widget::BoardWidget::new(dynamic_texture_manager.clone()),
-            dynamic_texture_manager,
+        let mut piece_textures = PieceTextures::new(dynamic_texture_manager);
+        piece_textures.preload(&[board_widget::PIECE_TEXTURE_SIZE]);
+
+        let piece_textures = Arc::new(Mutex::new(piece_textures));
+
+        let (tabs, active_tab) = cc
+            .storage
+            .and_then(|storage| load_tabs(storage, &piece_textures))
+            .unwrap_or_else(|| {
+                let tab = GameTab {
+                    assistant: Assistant::new(),
+                    board: BoardWidget::new(piece_textures.clone(), get_save_path(1)),
+                    computer: ComputerOpponent::new(),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    engine: ExternalEngine::new(),
+                    name: "Game 1".to_owned(),
+                    review: GameReviewer::new(),
+                };
+
+                (vec![tab], 0)
+            });
+
+        let mut client = Self {
+            active_tab,
+            next_tab_number: tabs.len() + 1,
+            piece_set_input: preferences.piece_set.clone(),
+            piece_textures,
+            preferences,
+            preferences_open: false,
+            tabs,
+            time_control_input: "300".to_owned(),
+        };
+
+        client.apply_preferences(&cc.egui_ctx);
+        client
+    }
+
+    fn board(&mut self) -> &mut BoardWidget {
+        &mut self.tabs[self.active_tab].board
+    }
+
+    /// Opens the game in a new tab and switches to it.
+    fn open_tab(&mut self, ctx: &egui::Context, name: String, game: Game) {
+        let save_path = get_save_path(self.next_tab_number);
+
+        self.next_tab_number += 1;
+        self.tabs.push(GameTab {
+            assistant: Assistant::new(),
+            board: BoardWidget::with_game(self.piece_textures.clone(), save_path, game),
+            computer: ComputerOpponent::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            engine: ExternalEngine::new(),
+            name,
+            review: GameReviewer::new(),
+        });
+        self.active_tab = self.tabs.len() - 1;
+        self.apply_preferences(ctx);
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() > 1 {
+            self.tabs.remove(index);
+            self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        }
+    }
+
+    /// Tabs of the open games, with buttons to open a new game and to copy
+    /// the current one for analysis.
+    fn tabs_ui(&mut self, ui: &mut egui::Ui) {
+        let mut closed_tab = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (index, tab) in self.tabs.iter().enumerate() {
+                if ui
+                    .selectable_label(index == self.active_tab, &tab.name)
+                    .clicked()
+                {
+                    self.active_tab = index;
+                }
+
+                if self.tabs.len() > 1 && ui.small_button("✖").clicked() {
+                    closed_tab = Some(index);
+                }
+
+                ui.separator();
+            }
+
+            if ui.button("➕ New game").clicked() {
+                let name = format!("Game {}", self.next_tab_number);
+                let game = Game::new(chess_logic::Board::new_with_standard_formation(
+                    chess_logic::Color::Black,
+                    chess_logic::Color::White,
+                ));
+
+                self.open_tab(ui.ctx(), name, game);
+            }
+
+            if ui
+                .button("⧉ Duplicate")
+                .on_hover_text("Copy the game into a new tab, e.g. for analysis")
+                .clicked()
+            {
+                let tab = &self.tabs[self.active_tab];
+                let name = format!("{} (copy)", tab.name);
+                let game = tab.board.game().clone();
+
+                self.open_tab(ui.ctx(), name, game);
+            }
+        });
+
+        if let Some(index) = closed_tab {
+            self.close_tab(index);
+        }
+    }
+
+    /// Hands the preferences to the parts of the client they configure.
+    fn apply_preferences(&mut self, ctx: &egui::Context) {
+        let preferences = &self.preferences;
+
+        ctx.set_visuals(preferences.visual_theme.visuals());
+
+        for tab in &mut self.tabs {
+            tab.board.set_visual_theme(preferences.visual_theme);
+            tab.board
+                .set_animation_duration(preferences.animation_duration);
+            tab.board.set_auto_queen(preferences.auto_queen);
+            tab.board.set_flipped(preferences.flipped);
+            tab.board.set_premoves_enabled(preferences.premoves_enabled);
+            tab.board.set_sound_volume(preferences.sound_volume);
+        }
+
+        let mut piece_textures = self.piece_textures.lock().unwrap();
+
+        if piece_textures.theme() != preferences.piece_set {
+            piece_textures.set_theme(preferences.piece_set.clone());
+        }
+    }
+
+    fn preferences_ui(&mut self, ctx: &egui::Context) {
+        // The auto-queen checkbox below the board edits the same setting.
+        self.preferences.auto_queen = self.board().auto_queen();
+
+        let before = self.preferences.clone();
+
+        preferences::preferences_window(
+            ctx,
+            &mut self.preferences_open,
+            &mut self.preferences,
+            &mut self.piece_set_input,
+        );
+
+        if self.preferences != before {
+            self.apply_preferences(ctx);
+        }
+    }
+
+    /// Ctrl+Z and Ctrl+Y (or Ctrl+Shift+Z) undo and redo moves.
+    ///
+    /// Left and right step through the history, home and end jump to the
+    /// start and the latest move. Only used while no widget has the focus,
+    /// since text fields have their own undo and the board uses the arrow
+    /// keys for its cursor.
+    fn handle_history_keys(&mut self, ctx: &egui::Context) {
+        if self.board().is_editing() || ctx.memory().focus().is_some() {
+            return;
+        }
+
+        let (undo, redo) = {
+            let input = ctx.input();
+            let modifiers = input.modifiers;
+
+            (
+                modifiers.command && !modifiers.shift && input.key_pressed(egui::Key::Z),
+                modifiers.command
+                    && (input.key_pressed(egui::Key::Y)
+                        || modifiers.shift && input.key_pressed(egui::Key::Z)),
+            )
+        };
+
+        if undo {
+            self.board().undo(ctx);
+        }
+
+        if redo {
+            self.board().redo(ctx);
+        }
+
+        let history = &self.board().game().history;
+        let (current, len) = (history.current(), history.len());
+
+        let target = {
+            let input = ctx.input();
+
+            if input.key_pressed(egui::Key::ArrowLeft) {
+                Some(current.saturating_sub(1))
+            } else if input.key_pressed(egui::Key::ArrowRight) {
+                Some(current + 1)
+            } else if input.key_pressed(egui::Key::Home) {
+                Some(0)
+            } else if input.key_pressed(egui::Key::End) {
+                Some(len)
+            } else {
+                None
+            }
+        };
+
+        if let Some(target) = target {
+            self.board().go_to_ply(ctx, target.min(len));
+        }
+    }
+
+    /// Lets the flag fall in every tab, also while the clock is not shown.
+    fn update_clocks(&mut self, ctx: &egui::Context) {
+        let now = ctx.input().time;
+
+        for tab in &mut self.tabs {
+            tab.board.game_mut().update_clock(now);
+        }
+
+        // The displayed time changes even without input.
+        if self
+            .tabs
+            .iter()
+            .any(|tab| tab.board.game().is_clock_running())
+        {
+            ctx.request_repaint();
         }
     }
 }
 
 impl eframe::App for ChessClient {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_history_keys(ctx);
+        self.update_clocks(ctx);
+
+        // Only the game in the active tab is played, the others wait.
+        let tab = &mut self.tabs[self.active_tab];
+        let locked_player = tab.computer.player(&tab.board);
+
+        tab.computer.update(ctx, &mut tab.board);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let locked_player = {
+            tab.engine.update(ctx, &mut tab.board);
+            locked_player.or_else(|| tab.engine.player(&tab.board))
+        };
+
+        tab.board.set_locked_player(locked_player);
+        tab.review.update(ctx, &mut tab.board);
+        tab.assistant.update(ctx, &tab.board);
+        tab.board.set_assistance(tab.assistant.marks());
+
+        let evaluation = tab.analysis_lines().first().map(|line| {
+            let game = tab.board.game();
+
+            match game.board.get_color_of_player(&game.to_move) {
+                chess_logic::Color::White => line.info.score,
+                chess_logic::Color::Black => -line.info.score,
+            }
+        });
+
+        tab.board.set_evaluation(evaluation);
+
+        egui::TopBottomPanel::top("menu panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("⚙ Settings").clicked() {
+                    self.preferences_open = !self.preferences_open;
+                }
+
+                ui.separator();
+                self.tabs_ui(ui);
+            });
+        });
+
+        self.preferences_ui(ctx);
+
+        let side_panel = egui::SidePanel::right("move list panel");
+
+        side_panel.show_animated(ctx, self.preferences.show_side_panel, |ui| {
+            let tab = &mut self.tabs[self.active_tab];
+
+            ui.add(clock_widget::clock_widget(
+                tab.board.game_mut(),
+                &mut self.time_control_input,
+            ));
+            ui.separator();
+            ui.add(computer_widget::computer_widget(&mut tab.computer));
+            ui.separator();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.add(engine_widget::engine_widget(&mut tab.engine));
+                ui.separator();
+            }
+
+            let analysis_lines = tab.analysis_lines();
+
+            if !analysis_lines.is_empty() {
+                let mut preview = None;
+
+                ui.add(analysis_widget::analysis_widget(
+                    analysis_lines,
+                    tab.board.game(),
+                    &mut preview,
+                ));
+                ui.separator();
+
+                if let Some(preview) = preview {
+                    tab.board.set_preview(preview);
+                }
+            }
+
+            ui.add(assistant_widget::assistant_widget(
+                &mut tab.assistant,
+                &tab.board,
+            ));
+            ui.separator();
+
+            let mut shown_move = None;
+
+            ui.add(report_widget::report_widget(
+                &mut tab.review,
+                &tab.board,
+                &mut shown_move,
+            ));
+            ui.separator();
+
+            if let Some(id) = shown_move {
+                tab.board.go_to_node(ctx, id);
+            }
+
+            let board = &mut tab.board;
+            ui.add(material_widget::material_widget(
+                board.game(),
+                self.piece_textures.clone(),
+            ));
+            ui.separator();
+
+            let history = &board.game().history;
+            let is_clock_running = board.game().is_clock_running();
+            let (can_undo, can_redo) = (
+                history.can_undo() && !is_clock_running,
+                history.can_redo() && !is_clock_running,
+            );
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(can_undo, egui::Button::new("⟲ Undo"))
+                    .clicked()
+                {
+                    board.undo(ctx);
+                }
+
+                if ui
+                    .add_enabled(can_redo, egui::Button::new("⟳ Redo"))
+                    .clicked()
+                {
+                    board.redo(ctx);
+                }
+
+                if ui
+                    .button("📋 Copy PGN")
+                    .on_hover_text("Copy the game with all variations as PGN")
+                    .clicked()
+                {
+                    ui.output().copied_text = pgn::format_pgn(board.game());
+                }
+            });
+
+            let mut action = None;
+
+            ui.add(move_list_widget::move_list_widget(
+                &board.game().history,
+                &mut action,
+            ));
+
+            match action {
+                Some(MoveListAction::GoTo(Some(id))) => board.go_to_node(ctx, id),
+                Some(MoveListAction::GoTo(None)) => board.go_to_ply(ctx, 0),
+                Some(MoveListAction::Promote(id)) => board.promote_variation(id),
+                Some(MoveListAction::Demote(id)) => board.demote_variation(id),
+                Some(MoveListAction::Delete(id)) => board.delete_from(ctx, id),
+                None => (),
+            }
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // ui.button("hello world");
-            // ui.add(PromoteWidget::new(self.dynamic_texture_manager.clone()));
-            self.board.ui(ui);
+            // ui.add(PromoteWidget::new(self.piece_textures.clone()));
+            let board = self.board();
+
+            board.ui(ui);
+
+            if let Some(error) = board.save_error() {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("Failed to save the game: {}", error),
+                );
+            }
+
+            if !board.is_editing() {
+                board.move_input_ui(ui);
+            }
+
+            board.editor_ui(ui);
+        });
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(PREFERENCES_KEY, self.preferences.serialize());
+
+        let ser_tabs = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let mut ser_tab = LinkedHashMap::new();
+
+                ser_tab.insert(
+                    Yaml::String("name".to_owned()),
+                    Yaml::String(tab.name.clone()),
+                );
+                ser_tab.insert(
+                    Yaml::String("game".to_owned()),
+                    serialize_game(tab.board.game()),
+                );
+
+                Yaml::Hash(ser_tab)
+            })
+            .collect();
+
+        let mut content = String::new();
+
+        YamlEmitter::new(&mut content)
+            .dump(&Yaml::Array(ser_tabs))
+            .unwrap();
+
+        storage.set_string(GAMES_KEY, content);
+        storage.set_string(ACTIVE_TAB_KEY, self.active_tab.to_string());
+    }
+}
+
+/// Save file of the tab with the number, `None` unless saving to files was
+/// enabled with [`SAVE_PATH_VAR`].
+#[cfg(not(target_arch = "wasm32"))]
+fn get_save_path(tab_number: usize) -> Option<String> {
+    let save_path = std::path::PathBuf::from(std::env::var(SAVE_PATH_VAR).ok()?);
+
+    if tab_number == 1 {
+        return Some(save_path.to_string_lossy().into_owned());
+    }
+
+    // The number goes before the extension, `game.yaml` becomes
+    // `game_2.yaml`.
+    let stem = save_path.file_stem()?.to_string_lossy();
+    let file_name = match save_path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, tab_number, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, tab_number),
+    };
+
+    Some(
+        save_path
+            .with_file_name(file_name)
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Games are only kept in the local storage on the web.
+#[cfg(target_arch = "wasm32")]
+fn get_save_path(_tab_number: usize) -> Option<String> {
+    None
+}
+
+/// Restores the tabs stored by [`eframe::App::save`], together with the index
+/// of the active one. Tabs that can't be read are skipped, so one broken game
+/// does not lose the others.
+fn load_tabs(
+    storage: &dyn eframe::Storage,
+    piece_textures: &Arc<Mutex<PieceTextures>>,
+) -> Option<(Vec<GameTab>, usize)> {
+    let content = storage.get_string(GAMES_KEY)?;
+    let docs = YamlLoader::load_from_str(&content).ok()?;
+    let mut tabs = Vec::new();
+
+    for (index, ser_tab) in docs.first()?.as_vec()?.iter().enumerate() {
+        let game = match deserialize_game(&ser_tab["game"]) {
+            Ok(game) => game,
+            Err(err) => {
+                eprintln!("Skipping saved tab {}: {}", index + 1, err);
+                continue;
+            }
+        };
+        // Numbered by the loaded tabs, as new tabs continue after them.
+        let tab_number = tabs.len() + 1;
+        let name = match ser_tab["name"].as_str() {
+            Some(name) => name.to_owned(),
+            None => format!("Game {}", tab_number),
+        };
+
+        tabs.push(GameTab {
+            assistant: Assistant::new(),
+            board: BoardWidget::with_game(piece_textures.clone(), get_save_path(tab_number), game),
+            computer: ComputerOpponent::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            engine: ExternalEngine::new(),
+            name,
+            review: GameReviewer::new(),
         });
     }
+
+    if tabs.is_empty() {
+        return None;
+    }
+
+    let active_tab = storage
+        .get_string(ACTIVE_TAB_KEY)
+        .and_then(|active_tab| active_tab.parse().ok())
+        .unwrap_or(0)
+        .min(tabs.len() - 1);
+
+    Some((tabs, active_tab))
 }
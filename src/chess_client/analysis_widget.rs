@@ -0,0 +1,140 @@
+use chess_logic::{Board, Color, Player};
+use eframe::egui;
+
+use crate::{
+    engine::{self, SearchInfo},
+    notation, rules, Game,
+};
+
+use super::board_widget::Preview;
+
+/// Line of an analysis with its moves already in SAN, so they are not
+/// formatted again every frame.
+pub struct AnalysisLine {
+    pub info: SearchInfo,
+    /// The moves of the line in SAN.
+    pub san: Vec<String>,
+}
+
+impl AnalysisLine {
+    /// Formats the moves of the line, which starts in the given position. The
+    /// line ends before the first move that can't be played.
+    pub fn new(mut info: SearchInfo, board: &Board, player: &Player) -> Self {
+        let mut position = board.clone();
+        let mut player = player.clone();
+        let mut san = Vec::new();
+
+        for mv in &info.pv {
+            let after = match rules::apply_move(&position, mv) {
+                Some(after) => after,
+                None => break,
+            };
+
+            san.push(notation::format_san(&position, &player, mv));
+            position = after;
+            player = rules::opponent_of(&player);
+        }
+
+        info.pv.truncate(san.len());
+
+        Self { info, san }
+    }
+}
+
+/// Lists the best lines of the analysing engine with their score, depth and
+/// nodes. Clicking the score of a line previews its first move, clicking a
+/// move of the line previews the position after it.
+pub fn analysis_widget<'a>(
+    lines: &'a [AnalysisLine],
+    game: &'a Game,
+    preview: &'a mut Option<Preview>,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            ui.label("Analysis");
+
+            for line in lines {
+                ui.horizontal_wrapped(|ui| line_ui(ui, line, game, preview));
+            }
+        })
+        .response
+    }
+}
+
+fn line_ui(ui: &mut egui::Ui, line: &AnalysisLine, game: &Game, preview: &mut Option<Preview>) {
+    let info = &line.info;
+    let first_move = match info.pv.first() {
+        Some(mv) => mv,
+        None => return,
+    };
+
+    let score = egui::RichText::new(engine::format_score(info.score)).strong();
+
+    if ui
+        .button(score)
+        .on_hover_text("Show the first move")
+        .clicked()
+    {
+        *preview = Some(Preview {
+            board: game.board.clone(),
+            mv: *first_move,
+        });
+    }
+
+    ui.label(
+        egui::RichText::new(format!(
+            "depth {}, {} nodes",
+            info.depth,
+            format_nodes(info.nodes)
+        ))
+        .weak(),
+    );
+
+    let history = &game.history;
+    let starts_with_white = matches!(
+        history.start().get_color_of_player(history.start_player()),
+        Color::White
+    );
+    // Plies since white's first move, to number the moves like PGN.
+    let mut ply = history.current() + usize::from(!starts_with_white);
+
+    for (index, (mv, san)) in info.pv.iter().zip(&line.san).enumerate() {
+        let number = match ply % 2 {
+            0 => Some(format!("{}.", ply / 2 + 1)),
+            _ if index == 0 => Some(format!("{}...", ply / 2 + 1)),
+            _ => None,
+        };
+
+        if let Some(number) = number {
+            ui.label(number);
+        }
+
+        if ui.small_button(san.as_str()).clicked() {
+            // The positions are only needed for the preview, the moves were
+            // checked when the line was formatted.
+            let after = info.pv[..=index]
+                .iter()
+                .try_fold(game.board.clone(), |position, mv| {
+                    rules::apply_move(&position, mv)
+                });
+
+            if let Some(after) = after {
+                *preview = Some(Preview {
+                    board: after,
+                    mv: *mv,
+                });
+            }
+        }
+
+        ply += 1;
+    }
+}
+
+/// Formats a node count like `950`, `48k` or `3.2M`.
+fn format_nodes(nodes: u64) -> String {
+    match nodes {
+        0..=9_999 => nodes.to_string(),
+        10_000..=999_999 => format!("{}k", nodes / 1_000),
+        _ => format!("{:.1}M", nodes as f64 / 1_000_000.0),
+    }
+}
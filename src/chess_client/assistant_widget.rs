@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::{
+    annotations::{AnnotationColor, Annotations, Arrow, SquareMark},
+    engine::{BackgroundSearch, SearchLimits},
+    notation,
+    rules::{self, Move},
+};
+
+use super::board_widget::BoardWidget;
+
+/// Short search, as the assistant should answer right away and only has to
+/// spot simple tactics.
+const ASSISTANT_LIMITS: SearchLimits = SearchLimits {
+    depth: 3,
+    multi_pv: 1,
+    randomness: 0,
+    time: Some(Duration::from_secs(1)),
+};
+
+/// Helps learning the game by hinting at the best move of the player to
+/// move and by showing what the opponent threatens.
+pub struct Assistant {
+    /// Search of the position the hint was asked for, with its FEN.
+    hint: Option<(BackgroundSearch, String)>,
+    /// The hint shows the whole move instead of only the piece to move.
+    pub show_hint_move: bool,
+    pub show_threats: bool,
+    /// Search of the best move of the opponent, as if it was their turn,
+    /// with the FEN of the position.
+    threats: Option<(BackgroundSearch, String)>,
+}
+
+impl Assistant {
+    pub fn new() -> Self {
+        Self {
+            hint: None,
+            show_hint_move: false,
+            show_threats: false,
+            threats: None,
+        }
+    }
+
+    /// Starts searching a hint for the shown position.
+    fn request_hint(&mut self, board: &BoardWidget) {
+        let game = board.game();
+        let search =
+            BackgroundSearch::start(game.board.clone(), game.to_move.clone(), ASSISTANT_LIMITS);
+
+        self.hint = Some((search, notation::format_fen(&game.board, &game.to_move)));
+    }
+
+    /// Drops the hint once the position changed and keeps the threats up to
+    /// date. Has to be called every frame.
+    pub fn update(&mut self, ctx: &egui::Context, board: &BoardWidget) {
+        let game = board.game();
+        let fen = notation::format_fen(&game.board, &game.to_move);
+        let is_active = !game.is_over() && !board.is_editing();
+
+        if !matches!(&self.hint, Some((_, hinted)) if *hinted == fen && is_active) {
+            self.hint = None;
+        }
+
+        // In check, the threat is obvious and the opponent could take the
+        // king, which the search does not expect.
+        let wants_threats =
+            self.show_threats && is_active && !rules::is_in_check(&game.board, &game.to_move);
+
+        if !wants_threats {
+            self.threats = None;
+        } else if !matches!(&self.threats, Some((_, searched)) if *searched == fen) {
+            let search = BackgroundSearch::start(
+                game.board.clone(),
+                rules::opponent_of(&game.to_move),
+                ASSISTANT_LIMITS,
+            );
+
+            self.threats = Some((search, fen));
+        }
+
+        for (search, _) in self.hint.iter_mut().chain(self.threats.iter_mut()) {
+            search.poll();
+
+            if !search.is_finished() {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    fn hint_move(&self) -> Option<&Move> {
+        match &self.hint {
+            Some((search, _)) if search.is_finished() => search.latest()?.best_move(),
+            _ => None,
+        }
+    }
+
+    fn threat_move(&self) -> Option<&Move> {
+        match &self.threats {
+            Some((search, _)) if search.is_finished() => search.latest()?.best_move(),
+            _ => None,
+        }
+    }
+
+    /// Marks for the board: the piece to move of the hint, or its move, in
+    /// green and the threat of the opponent in red.
+    pub fn marks(&self) -> Annotations {
+        let mut marks = Annotations::default();
+
+        if let Some(mv) = self.hint_move() {
+            match self.show_hint_move {
+                true => marks.arrows.push(Arrow {
+                    color: AnnotationColor::Green,
+                    from: mv.from,
+                    to: mv.to,
+                }),
+                false => marks.squares.push(SquareMark {
+                    color: AnnotationColor::Green,
+                    pos: mv.from,
+                }),
+            }
+        }
+
+        if let Some(mv) = self.threat_move() {
+            marks.arrows.push(Arrow {
+                color: AnnotationColor::Red,
+                from: mv.from,
+                to: mv.to,
+            });
+        }
+
+        marks
+    }
+}
+
+/// Button for a hint and the switch for the threats, each with a short
+/// description of what is marked on the board.
+pub fn assistant_widget<'a>(
+    assistant: &'a mut Assistant,
+    board: &'a BoardWidget,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            let game = board.game();
+            let is_active = !game.is_over() && !board.is_editing();
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(is_active, egui::Button::new("💡 Hint"))
+                    .on_hover_text("Mark the piece the computer would move")
+                    .clicked()
+                {
+                    assistant.request_hint(board);
+                }
+
+                ui.checkbox(&mut assistant.show_hint_move, "Show the move");
+            });
+
+            if let Some(mv) = assistant.hint_move() {
+                ui.label(match assistant.show_hint_move {
+                    true => format!(
+                        "Hint: {}",
+                        notation::format_san(&game.board, &game.to_move, mv)
+                    ),
+                    false => format!(
+                        "Hint: move the piece on {}",
+                        notation::square_name(&game.board, mv.from)
+                    ),
+                });
+            } else if assistant.hint.is_some() {
+                ui.label("Looking for a hint…");
+            }
+
+            ui.checkbox(&mut assistant.show_threats, "Show threats")
+                .on_hover_text("Mark what the opponent would play if it were their turn");
+
+            if let Some(mv) = assistant.threat_move() {
+                let opponent = rules::opponent_of(&game.to_move);
+
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!(
+                        "Threat: {}",
+                        notation::format_san(&game.board, &opponent, mv)
+                    ),
+                );
+            }
+        })
+        .response
+    }
+}
@@ -1,64 +1,179 @@
 use std::{
     fs,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use chess_logic::{board::PieceInstance, Board, Color, InfoBoard, Piece, Player};
+use chess_logic::{Board, Color, InfoBoard, Piece, Player};
 use eframe::{
-    egui::{self, Image, Sense, Widget},
-    emath::{pos2, Rect},
-    epaint::{tessellator::Path, Color32, Rounding, Stroke},
+    egui::{self, Image, Key, Modifiers, PointerButton, Sense, Widget},
+    emath::{pos2, Align2, Pos2, Rect},
+    epaint::{tessellator::Path, Color32, FontId, Rounding, Stroke},
 };
 use yaml_rust::{YamlEmitter, YamlLoader};
 
-use crate::{deserialize_game, serialize_game};
+use crate::{
+    annotations::{AnnotationColor, Annotations, Arrow, SquareMark},
+    deserialize_game, engine,
+    history::NodeId,
+    notation,
+    review::GameReport,
+    rules::{self, Move, Pos},
+    serialize_game, Game,
+};
 
-use super::promote_widget::promote_widget;
+use super::{
+    piece_animation::{PieceAnimation, DEFAULT_ANIMATION_DURATION},
+    piece_textures::PieceTextures,
+    position_editor::{EditorAction, PositionEditor},
+    promote_widget::{promote_widget, PromotionChoice},
+    sound::{Sound, DEFAULT_SOUND_VOLUME},
+    theme::{BoardColors, VisualTheme},
+};
 
 const PIECE_SIZE: usize = 40;
-const PIECE_TEXTURE_SIZE: egui_extras::dynamic_texture_manager::TextureSize =
+pub const PIECE_TEXTURE_SIZE: egui_extras::dynamic_texture_manager::TextureSize =
     (PIECE_SIZE, PIECE_SIZE);
 const PIECE_SIZE_VEC: egui::Vec2 =
     egui::vec2(PIECE_TEXTURE_SIZE.0 as f32, PIECE_TEXTURE_SIZE.1 as f32);
+/// Width of the evaluation bar and its distance to the board.
+const EVALUATION_BAR_WIDTH: f32 = 16.0;
+const EVALUATION_BAR_GAP: f32 = 6.0;
+/// Approximate height of the promotion picker, used to keep it on the board
+/// when promoting on the bottom rank.
+const PROMOTION_PICKER_HEIGHT: f32 = 4.0 * PIECE_SIZE as f32 + 40.0;
 
-pub struct BoardWidget {
-    board: chess_logic::Board,
-    dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>,
+/// Source of the ids that keep the animations of the boards apart.
+static NEXT_BOARD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A pawn reached the last rank and waits for the player to pick a piece.
+struct PendingPromotion {
+    before: Board,
+    from: Pos,
+    to: Pos,
 }
 
-impl BoardWidget {
-    pub fn new(dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>) -> Self {
-        fn ins(player: Player, piece: Piece) -> Option<PieceInstance> {
-            let mut ins = PieceInstance::new(player, piece);
-            ins.was_moved = true;
+/// Position of an engine line, shown instead of the game until the board is
+/// clicked.
+pub struct Preview {
+    pub board: Board,
+    /// Move drawn as an arrow, usually the one leading to the position or the
+    /// next one of the line.
+    pub mv: Move,
+}
 
-            Some(ins)
-        }
+pub struct BoardWidget {
+    animation: Option<PieceAnimation>,
+    animation_count: u64,
+    animation_duration: f32,
+    annotation_start: Option<Pos>,
+    /// Hint and threats of the assistant, painted like the annotations but
+    /// not saved with the game.
+    assistance: Annotations,
+    auto_queen: bool,
+    checked_king_pos: Option<Pos>,
+    colors: BoardColors,
+    /// Square focused with the arrow keys while the board has focus.
+    cursor: Option<Pos>,
+    /// Set while a position is being set up instead of played.
+    editor: Option<PositionEditor>,
+    /// Shown in the bar next to the board, in centipawns from white's point
+    /// of view. `None` hides the bar.
+    evaluation: Option<i32>,
+    /// Whether the board is rotated by 180 degrees.
+    flipped: bool,
+    game: Game,
+    /// Keeps the animations of the boards of different tabs apart.
+    id: egui::Id,
+    /// Player whose moves are made by the computer or an engine, so the
+    /// user can't move their pieces.
+    locked_player: Option<Player>,
+    move_input: String,
+    move_input_error: Option<String>,
+    /// Top left corner of the board on the screen, taken from the layout
+    /// every time the board is shown.
+    origin: Pos2,
+    pending_promotion: Option<PendingPromotion>,
+    piece_textures: Arc<Mutex<PieceTextures>>,
+    premove_from: Option<Pos>,
+    premoves: Vec<Move>,
+    premoves_enabled: bool,
+    preview: Option<Preview>,
+    /// Why the game could not be written to the save file.
+    save_error: Option<String>,
+    /// File the game is written to after every move, in addition to the
+    /// eframe storage. Always `None` on the web.
+    save_path: Option<String>,
+    selected_pos: Option<Pos>,
+    /// `0.0` mutes the sounds of the moves.
+    sound_volume: f32,
+}
 
-        let board =
-            if let Ok(file_content) = fs::read_to_string("C:/Users/Elias/Desktop/chess_test.yaml") {
-                let ser_game = &YamlLoader::load_from_str(&file_content).unwrap()[0];
+impl BoardWidget {
+    /// Board for the game saved at `save_path`, or a new game if there is
+    /// none or it can't be read.
+    pub fn new(piece_textures: Arc<Mutex<PieceTextures>>, save_path: Option<String>) -> Self {
+        let saved_game = save_path
+            .as_ref()
+            .and_then(|save_path| fs::read_to_string(save_path).ok())
+            .and_then(|file_content| YamlLoader::load_from_str(&file_content).ok())
+            .and_then(|docs| deserialize_game(docs.first()?).ok());
 
-                deserialize_game(ser_game).unwrap()
-            } else {
-                chess_logic::Board::new_with_standard_formation(
-                    chess_logic::Color::Black,
-                    chess_logic::Color::White,
-                )
-            };
+        let game = saved_game.unwrap_or_else(|| {
+            Game::new(chess_logic::Board::new_with_standard_formation(
+                chess_logic::Color::Black,
+                chess_logic::Color::White,
+            ))
+        });
 
-        // let mut board = Board::new(Color::Black, Color::White);
-        // board.set(0, 7, ins(Player::You, Piece::King));
-        // board.set(1, 6, ins(Player::You, Piece::Bishop));
-        // board.set(7, 0, ins(Player::Opponent, Piece::King));
-        // board.set(4, 4, ins(Player::Opponent, Piece::Pawn));
+        Self::with_game(piece_textures, save_path, game)
+    }
 
+    pub fn with_game(
+        piece_textures: Arc<Mutex<PieceTextures>>,
+        save_path: Option<String>,
+        game: Game,
+    ) -> Self {
         Self {
-            board,
-            dynamic_texture_manager,
+            animation: None,
+            animation_count: 0,
+            animation_duration: DEFAULT_ANIMATION_DURATION,
+            annotation_start: None,
+            assistance: Annotations::default(),
+            auto_queen: false,
+            checked_king_pos: rules::get_checked_king_pos(&game.board),
+            colors: VisualTheme::Light.board_colors(),
+            cursor: None,
+            editor: None,
+            evaluation: None,
+            flipped: false,
+            game,
+            id: egui::Id::new("board").with(NEXT_BOARD_ID.fetch_add(1, Ordering::Relaxed)),
+            locked_player: None,
+            move_input: String::new(),
+            move_input_error: None,
+            origin: Pos2::ZERO,
+            pending_promotion: None,
+            piece_textures,
+            premove_from: None,
+            premoves: Vec::new(),
+            premoves_enabled: true,
+            preview: None,
+            save_error: None,
+            save_path,
+            selected_pos: None,
+            sound_volume: DEFAULT_SOUND_VOLUME,
         }
     }
 
+    /// Sets how long pieces take to move to their new square. `0.0` disables
+    /// the animation.
+    pub fn set_animation_duration(&mut self, seconds: f32) {
+        self.animation_duration = seconds;
+    }
+
     fn paint_piece_at(
         &mut self,
         ui: &mut egui::Ui,
@@ -66,103 +181,1141 @@ impl BoardWidget {
         piece_color: &chess_logic::Color,
         rect: &Rect,
     ) {
-        let mut dynamic_texture_manager = self.dynamic_texture_manager.lock().unwrap();
+        self.paint_tinted_piece_at(ui, piece, piece_color, rect, Color32::WHITE);
+    }
 
+    fn paint_tinted_piece_at(
+        &mut self,
+        ui: &mut egui::Ui,
+        piece: &chess_logic::Piece,
+        piece_color: &chess_logic::Color,
+        rect: &Rect,
+        tint: Color32,
+    ) {
         let texture_id =
-            match (piece, piece_color) {
-                (chess_logic::Piece::Bishop, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/bishop_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::King, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/king_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Knight, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/knight_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Pawn, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/pawn_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Queen, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/queen_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Rook, chess_logic::Color::Black) => dynamic_texture_manager
-                    .load_sized("src/assets/rook_black.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Bishop, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/bishop_white.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::King, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/king_white.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Knight, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/knight_white.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Pawn, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/pawn_white.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Queen, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/queen_white.svg", &PIECE_TEXTURE_SIZE),
-                (chess_logic::Piece::Rook, chess_logic::Color::White) => dynamic_texture_manager
-                    .load_sized("src/assets/rook_white.svg", &PIECE_TEXTURE_SIZE),
+            self.piece_textures
+                .lock()
+                .unwrap()
+                .get(piece, piece_color, &PIECE_TEXTURE_SIZE);
+
+        Image::new(texture_id, PIECE_SIZE_VEC)
+            .tint(tint)
+            .paint_at(ui, *rect);
+    }
+
+    /// Animates the change from `before` to the position currently on the
+    /// board.
+    fn animate_from(&mut self, ctx: &egui::Context, before: &Board) {
+        if self.animation_duration <= 0.0 {
+            self.animation = None;
+            return;
+        }
+
+        self.animation_count += 1;
+
+        let id = egui::Id::new("piece animation")
+            .with(self.id)
+            .with(self.animation_count);
+        let animation = PieceAnimation::between(before, &self.game.board, id);
+
+        animation.start(ctx);
+        self.animation = Some(animation);
+    }
+
+    fn is_animated_to(&self, pos: Pos) -> bool {
+        match &self.animation {
+            Some(animation) => animation.is_destination(pos),
+            None => false,
+        }
+    }
+
+    fn paint_animation(&mut self, ui: &mut egui::Ui) {
+        let animation = match self.animation.take() {
+            Some(animation) => animation,
+            None => return,
+        };
+
+        let progress = animation.progress(ui.ctx(), self.animation_duration);
+
+        if progress >= 1.0 {
+            return;
+        }
+
+        for fading in &animation.fading {
+            let rect = self.get_square_rect(fading.pos);
+            let tint = Color32::from_white_alpha(((1.0 - progress) * 255.0) as u8);
+
+            self.paint_tinted_piece_at(ui, &fading.piece, &fading.color, &rect, tint);
+        }
+
+        for moving in &animation.moving {
+            let from = self.get_square_rect(moving.from);
+            let to = self.get_square_rect(moving.to);
+            let rect = from.translate((to.min - from.min) * progress);
+
+            self.paint_piece_at(ui, &moving.piece, &moving.color, &rect);
+        }
+
+        self.animation = Some(animation);
+    }
+
+    fn paint_last_move_at(&self, ui: &mut egui::Ui, rect: &Rect) {
+        ui.painter()
+            .rect_filled(*rect, Rounding::none(), self.colors.last_move);
+    }
+
+    fn paint_check_at(&self, ui: &mut egui::Ui, rect: &Rect) {
+        ui.painter().circle(
+            rect.center(),
+            PIECE_SIZE as f32 / 2.0,
+            self.colors.check,
+            Stroke::none(),
+        );
+    }
+
+    fn update_selected(&mut self, x: i8, y: i8) {
+        // The board lets any piece be selected and moved, whoever's turn it is.
+        let is_opponent_piece = match self.game.board.get(x, y) {
+            Some(ins) => ins.player != self.game.to_move,
+            None => false,
+        };
+
+        if is_opponent_piece {
+            return self.deselect();
+        }
+
+        self.game.board.update_selected(x, y);
+        self.selected_pos = self.game.board.get_selected().map(|_| (x, y));
+    }
+
+    /// Selects the piece on the square or moves the selected piece there, like
+    /// clicking on it does.
+    fn activate_square(&mut self, ctx: &egui::Context, x: i8, y: i8) {
+        if let Some(from) = self.premove_from.take() {
+            if from != (x, y) {
+                self.premoves.push(Move::new(from, (x, y)));
+            }
+
+            return;
+        }
+
+        if self.selected_pos.is_none() && self.is_premove_piece_at((x, y)) {
+            self.premove_from = Some((x, y));
+            return;
+        }
+
+        if self.is_locked_turn() {
+            return;
+        }
+
+        let from = match self.selected_pos {
+            Some(from) => from,
+            None => return self.update_selected(x, y),
+        };
+
+        // The selection can be left over from before the turn changed.
+        let is_own_piece = match self.game.board.get(from.0, from.1) {
+            Some(ins) => ins.player == self.game.to_move,
+            None => false,
+        };
+
+        if !is_own_piece {
+            return self.update_selected(x, y);
+        }
+
+        let before = self.game.board.clone();
+
+        if self.game.board.move_selected_to(x, y) {
+            self.finish_move(ctx, before, from, (x, y), None);
+        } else {
+            self.update_selected(x, y);
+        }
+    }
+
+    /// Plays the move as if it was made with the mouse. Returns `false` if the
+    /// board did not accept it.
+    pub fn play_move(&mut self, ctx: &egui::Context, mv: &Move) -> bool {
+        if self.game.is_over() {
+            return false;
+        }
+
+        let before = self.game.board.clone();
+
+        self.update_selected(mv.from.0, mv.from.1);
+
+        if !self.game.board.move_selected_to(mv.to.0, mv.to.1) {
+            self.deselect();
+            return false;
+        }
+
+        self.finish_move(ctx, before, mv.from, mv.to, mv.promotion);
+
+        true
+    }
+
+    /// Completes a move the board accepted. If a pawn reached the last rank
+    /// and neither `promotion` nor auto-queen decide the piece, the move stays
+    /// pending until the player picks one.
+    fn finish_move(
+        &mut self,
+        ctx: &egui::Context,
+        before: Board,
+        from: Pos,
+        to: Pos,
+        promotion: Option<Piece>,
+    ) {
+        if self.game.board.get_promote_pos().is_some() {
+            let promotion = match promotion {
+                Some(piece) => Some(piece),
+                None if self.auto_queen => Some(Piece::Queen),
+                None => None,
             };
 
-        Image::new(texture_id, PIECE_SIZE_VEC).paint_at(ui, *rect);
+            match promotion {
+                Some(piece) => self.game.board.promote_piece_to(piece),
+                None => {
+                    self.selected_pos = None;
+                    self.pending_promotion = Some(PendingPromotion { before, from, to });
+                    return;
+                }
+            }
+        }
+
+        self.on_moved(ctx, &before, from, to);
+    }
+
+    fn promotion_ui(&mut self, ui: &mut egui::Ui, (x, y): Pos) {
+        let color = match self.game.board.get(x, y) {
+            Some(ins) => self.game.board.get_color_of_player(&ins.player).clone(),
+            None => return,
+        };
+
+        let rect = self.get_square_rect((x, y));
+        let pos = if rect.min.y == self.origin.y {
+            rect.min
+        } else {
+            pos2(rect.min.x, rect.max.y - PROMOTION_PICKER_HEIGHT)
+        };
+
+        let mut choice = None;
+
+        ui.add(promote_widget(
+            &mut choice,
+            color,
+            pos,
+            self.piece_textures.clone(),
+        ));
+
+        match choice {
+            Some(PromotionChoice::Piece(piece)) => {
+                self.game.board.promote_piece_to(piece);
+
+                match self.pending_promotion.take() {
+                    Some(pending) => {
+                        self.on_moved(ui.ctx(), &pending.before, pending.from, pending.to)
+                    }
+                    // The game was loaded with a promotion in progress.
+                    None => self.on_board_changed(),
+                }
+            }
+            Some(PromotionChoice::Cancel) => {
+                if let Some(pending) = self.pending_promotion.take() {
+                    self.game.board = pending.before;
+                    self.deselect();
+                }
+            }
+            None => (),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Why the game could not be saved the last time, `None` after it was.
+    pub fn save_error(&self) -> Option<&str> {
+        self.save_error.as_deref()
     }
 
+    /// Replaces the game, e.g. with one restored from storage. A game that
+    /// starts in a finished position is over right away.
+    pub fn set_game(&mut self, mut game: Game) {
+        if game.result.is_none() {
+            game.update_result();
+        }
+
+        self.cancel_premoves();
+        self.animation = None;
+        self.pending_promotion = None;
+        self.game = game;
+        self.deselect();
+        self.on_board_changed();
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Shows the position after the given ply of the history.
+    pub fn go_to_ply(&mut self, ctx: &egui::Context, index: usize) {
+        if index == self.game.history.current() || self.game.is_clock_running() {
+            return;
+        }
+
+        let before = self.game.board.clone();
+
+        self.pending_promotion = None;
+        self.selected_pos = None;
+        self.cancel_premoves();
+        self.game.go_to_ply(index);
+        self.on_board_changed();
+        self.animate_from(ctx, &before);
+    }
+
+    /// Shows the position after the node of the history tree, switching to
+    /// its variation.
+    pub fn go_to_node(&mut self, ctx: &egui::Context, id: NodeId) {
+        if self.game.history.current_node() == Some(id) || self.game.is_clock_running() {
+            return;
+        }
+
+        let before = self.game.board.clone();
+
+        self.pending_promotion = None;
+        self.selected_pos = None;
+        self.cancel_premoves();
+        self.game.go_to_node(id);
+        self.on_board_changed();
+        self.animate_from(ctx, &before);
+    }
+
+    /// Annotates the moves with the glyphs and comments of the review.
+    pub fn apply_report(&mut self, report: &GameReport) {
+        report.annotate(&mut self.game);
+        self.autosave();
+    }
+
+    pub fn promote_variation(&mut self, id: NodeId) {
+        if self.game.history.promote_variation(id) {
+            self.autosave();
+        }
+    }
+
+    pub fn demote_variation(&mut self, id: NodeId) {
+        if self.game.history.demote_variation(id) {
+            self.autosave();
+        }
+    }
+
+    /// Deletes the move with every move after it.
+    pub fn delete_from(&mut self, ctx: &egui::Context, id: NodeId) {
+        if self.game.is_clock_running() {
+            return;
+        }
+
+        let before = self.game.board.clone();
+
+        self.pending_promotion = None;
+        self.game.delete_from(id);
+        self.on_history_changed(ctx, &before);
+    }
+
+    /// Takes back the last move. A promotion that is still waiting for a
+    /// piece is cancelled instead.
+    pub fn undo(&mut self, ctx: &egui::Context) {
+        if let Some(pending) = self.pending_promotion.take() {
+            self.game.board = pending.before;
+            self.deselect();
+            return;
+        }
+
+        if self.game.is_clock_running() {
+            return;
+        }
+
+        let before = self.game.board.clone();
+
+        if self.game.undo() {
+            self.on_history_changed(ctx, &before);
+        }
+    }
+
+    pub fn redo(&mut self, ctx: &egui::Context) {
+        if self.pending_promotion.is_some() || self.game.is_clock_running() {
+            return;
+        }
+
+        let before = self.game.board.clone();
+
+        if self.game.redo() {
+            self.on_history_changed(ctx, &before);
+        }
+    }
+
+    fn on_history_changed(&mut self, ctx: &egui::Context, before: &Board) {
+        self.selected_pos = None;
+        self.cancel_premoves();
+        self.on_board_changed();
+        self.animate_from(ctx, before);
+        self.autosave();
+    }
+
+    pub fn auto_queen(&self) -> bool {
+        self.auto_queen
+    }
+
+    pub fn set_auto_queen(&mut self, auto_queen: bool) {
+        self.auto_queen = auto_queen;
+    }
+
+    pub fn set_sound_volume(&mut self, volume: f32) {
+        self.sound_volume = volume;
+    }
+
+    fn on_moved(&mut self, ctx: &egui::Context, before: &Board, from: Pos, to: Pos) {
+        let now = ctx.input().time;
+
+        self.selected_pos = None;
+
+        if let Some(clock) = &mut self.game.clock {
+            clock.press(now);
+        }
+
+        self.game.record_move(before, from, to);
+
+        if self.game.is_over() {
+            if let Some(clock) = &mut self.game.clock {
+                clock.stop(now);
+            }
+        }
+
+        if let Some(id) = self.game.history.current_node() {
+            let san = &self.game.history.ply(id).san;
+
+            Sound::of_move(san, self.game.is_over()).play(self.sound_volume);
+        }
+
+        self.on_board_changed();
+        self.animate_from(ctx, before);
+        self.autosave();
+        self.play_premove(ctx);
+    }
+
+    /// Checks whether a click on the square starts a premove, which is the
+    /// case for the user's pieces while the computer or an engine thinks.
+    fn is_premove_piece_at(&self, (x, y): Pos) -> bool {
+        if !self.premoves_enabled || self.game.is_over() || !self.is_locked_turn() {
+            return false;
+        }
+
+        match self.get_premove_board().get(x, y) {
+            Some(ins) => ins.player != self.game.to_move,
+            None => false,
+        }
+    }
+
+    /// The board with all queued premoves applied, ignoring whether they are
+    /// legal. Used to queue premoves of pieces that were already premoved.
+    fn get_premove_board(&self) -> Board {
+        let mut board = self.game.board.clone();
+
+        for premove in &self.premoves {
+            let ins = board.get(premove.from.0, premove.from.1).cloned();
+
+            board.set(premove.from.0, premove.from.1, None);
+            board.set(premove.to.0, premove.to.1, ins);
+        }
+
+        board
+    }
+
+    /// Plays the first queued premove once the computer or engine moved. If
+    /// it is not legal anymore, all premoves are discarded.
+    fn play_premove(&mut self, ctx: &egui::Context) {
+        let premove = match self.premoves.first() {
+            Some(premove) => *premove,
+            None => return,
+        };
+
+        if self.game.is_over() {
+            self.premoves.clear();
+            return;
+        }
+
+        // The premoves wait for the move of the opponent.
+        if self.is_locked_turn() {
+            return;
+        }
+
+        // Premoves to the last rank promote to a queen.
+        let legal_move = rules::legal_moves(&self.game.board, &self.game.to_move)
+            .into_iter()
+            .find(|mv| {
+                mv.from == premove.from
+                    && mv.to == premove.to
+                    && matches!(mv.promotion, None | Some(Piece::Queen))
+            });
+
+        self.premoves.remove(0);
+
+        match legal_move {
+            Some(mv) => {
+                self.play_move(ctx, &mv);
+            }
+            None => self.premoves.clear(),
+        }
+    }
+
+    /// Premoves can only be made while a player is locked, as there is no
+    /// opponent to wait for otherwise.
+    pub fn set_locked_player(&mut self, locked_player: Option<Player>) {
+        if locked_player.is_none() {
+            self.cancel_premoves();
+        }
+
+        self.locked_player = locked_player;
+    }
+
+    /// Whether the player to move is moved by someone else than the user.
+    fn is_locked_turn(&self) -> bool {
+        self.locked_player.as_ref() == Some(&self.game.to_move)
+    }
+
+    pub fn set_premoves_enabled(&mut self, premoves_enabled: bool) {
+        self.premoves_enabled = premoves_enabled;
+        self.premoves.clear();
+        self.premove_from = None;
+    }
+
+    fn cancel_premoves(&mut self) -> bool {
+        let had_premoves = !self.premoves.is_empty() || self.premove_from.is_some();
+
+        self.premoves.clear();
+        self.premove_from = None;
+
+        had_premoves
+    }
+
+    fn is_premove_square(&self, pos: Pos) -> bool {
+        self.premove_from == Some(pos)
+            || self
+                .premoves
+                .iter()
+                .any(|premove| premove.from == pos || premove.to == pos)
+    }
+
+    fn paint_premove_at(&self, ui: &mut egui::Ui, rect: &Rect) {
+        ui.painter()
+            .rect_filled(*rect, Rounding::none(), self.colors.premove);
+    }
+
+    fn deselect(&mut self) {
+        // The board has no way to clear the selection directly, but selecting
+        // an empty square does not select anything.
+        let empty_square = (0..self.game.board.height())
+            .flat_map(|y| (0..self.game.board.width()).map(move |x| (x, y)))
+            .find(|(x, y)| self.game.board.get(*x, *y).is_none());
+
+        if let Some((x, y)) = empty_square {
+            self.update_selected(x, y);
+        }
+
+        self.selected_pos = None;
+    }
+
+    /// Arrow keys move the cursor, enter acts like a click on the square under
+    /// the cursor and escape clears the selection.
+    fn handle_keyboard_input(&mut self, ui: &egui::Ui) {
+        let (width, height) = (self.game.board.width(), self.game.board.height());
+        let (mut x, mut y) = self.cursor.unwrap_or((width / 2, height - 1));
+        // The arrow keys move the cursor on the screen, not on the board.
+        let step = if self.flipped { -1 } else { 1 };
+
+        let (enter, escape) = {
+            let input = ui.input();
+
+            if input.key_pressed(Key::ArrowLeft) {
+                x = (x - step).clamp(0, width - 1);
+            }
+            if input.key_pressed(Key::ArrowRight) {
+                x = (x + step).clamp(0, width - 1);
+            }
+            if input.key_pressed(Key::ArrowUp) {
+                y = (y - step).clamp(0, height - 1);
+            }
+            if input.key_pressed(Key::ArrowDown) {
+                y = (y + step).clamp(0, height - 1);
+            }
+
+            (
+                input.key_pressed(Key::Enter),
+                input.key_pressed(Key::Escape),
+            )
+        };
+
+        self.cursor = Some((x, y));
+
+        if enter {
+            self.activate_square(ui.ctx(), x, y);
+        }
+
+        if escape {
+            self.deselect();
+        }
+    }
+
+    fn get_board_rect(&self) -> Rect {
+        Rect::from_min_size(
+            self.origin,
+            egui::vec2(
+                (self.game.board.width() as usize * PIECE_SIZE) as f32,
+                (self.game.board.height() as usize * PIECE_SIZE) as f32,
+            ),
+        )
+    }
+
+    /// Darkens the board and shows how the game ended.
+    fn paint_result_overlay(&self, ui: &mut egui::Ui) {
+        let result = match &self.game.result {
+            Some(result) => result,
+            None => return,
+        };
+
+        let rect = self.get_board_rect();
+        let text = format!(
+            "{}\n{}",
+            result.pgn_result(&self.game.board),
+            result.description(&self.game.board)
+        );
+
+        ui.painter()
+            .rect_filled(rect, Rounding::none(), Color32::from_black_alpha(160));
+        ui.painter().text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::proportional(22.0),
+            Color32::WHITE,
+        );
+    }
+
+    fn paint_cursor(&self, ui: &mut egui::Ui) {
+        if let Some((x, y)) = self.cursor {
+            ui.painter().rect_stroke(
+                self.get_square_rect((x, y)).shrink(1.5),
+                Rounding::none(),
+                Stroke::new(3.0, self.colors.cursor),
+            );
+        }
+    }
+
+    /// Text box below the board that accepts moves in SAN (`Nf3`) or UCI
+    /// (`e7e8q`).
+    pub fn move_input_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Move:");
+
+            let response = ui.text_edit_singleline(&mut self.move_input);
+
+            if response.lost_focus() && ui.input().key_pressed(Key::Enter) {
+                self.submit_move_input(ui.ctx());
+
+                // Keep the focus, so the next move can be typed right away.
+                response.request_focus();
+            }
+
+            ui.checkbox(&mut self.auto_queen, "Auto-queen");
+
+            if let Some(error) = &self.move_input_error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+    }
+
+    fn submit_move_input(&mut self, ctx: &egui::Context) {
+        let text = self.move_input.trim().to_owned();
+
+        if text.is_empty() {
+            return;
+        }
+
+        if self.is_locked_turn() {
+            self.move_input_error = Some("It is not your turn".to_owned());
+            return;
+        }
+
+        let mv = notation::parse_move(&self.game.board, &self.game.to_move, &text);
+
+        self.move_input_error = match mv {
+            Some(mv) if self.play_move(ctx, &mv) => None,
+            _ => Some(format!("'{}' is not a legal move", text)),
+        };
+
+        if self.move_input_error.is_none() {
+            self.move_input.clear();
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    /// Switches to the position editor, starting from the shown position.
+    pub fn start_editing(&mut self) {
+        self.cancel_premoves();
+        self.deselect();
+        self.pending_promotion = None;
+        self.preview = None;
+        self.editor = Some(PositionEditor::new(
+            self.game.board.clone(),
+            &self.game.to_move,
+        ));
+    }
+
+    /// Palette and settings of the position editor, or the button to open
+    /// it.
+    pub fn editor_ui(&mut self, ui: &mut egui::Ui) {
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None => {
+                if ui.button("✏ Set up position").clicked() {
+                    self.start_editing();
+                }
+
+                return;
+            }
+        };
+
+        match editor.ui(ui, self.piece_textures.clone()) {
+            Some(EditorAction::Start(board, to_move)) => {
+                self.editor = None;
+                self.set_game(Game::new_with_player(board, to_move));
+                self.autosave();
+            }
+            Some(EditorAction::Cancel) => self.editor = None,
+            None => (),
+        }
+    }
+
+    /// Board of the position editor. A click applies the selected tool, a
+    /// right click removes the piece.
+    fn editor_board_ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let board = match &self.editor {
+            Some(editor) => editor.board.clone(),
+            None => return ui.allocate_rect(self.get_board_rect(), Sense::hover()),
+        };
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let rect = self.get_square_rect((x, y));
+
+                ui.painter()
+                    .rect_filled(rect, Rounding::none(), self.get_square_bg_color(y, x));
+
+                if let Some(ins) = board.get(x, y) {
+                    let piece_color = board.get_color_of_player(&ins.player).clone();
+
+                    self.paint_piece_at(ui, &ins.piece, &piece_color, &rect);
+                }
+
+                let square = ui.allocate_rect(rect, Sense::click());
+
+                if let Some(editor) = &mut self.editor {
+                    if square.clicked() {
+                        editor.click((x, y));
+                    } else if square.secondary_clicked() {
+                        editor.erase((x, y));
+                    }
+                }
+            }
+        }
+
+        ui.allocate_rect(self.get_board_rect(), Sense::hover())
+    }
+
+    /// Has to be called after the position on the board changed.
+    fn on_board_changed(&mut self) {
+        self.checked_king_pos = rules::get_checked_king_pos(&self.game.board);
+        self.preview = None;
+    }
+
+    /// Shows the position of an engine line instead of the game, until the
+    /// board is clicked or the game changes.
+    pub fn set_preview(&mut self, preview: Preview) {
+        self.deselect();
+        self.preview = Some(preview);
+    }
+
+    pub fn is_previewing(&self) -> bool {
+        self.preview.is_some()
+    }
+
+    /// Board showing the preview, with a frame to tell it from the game. A
+    /// click returns to the game.
+    fn preview_board_ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let preview = match &self.preview {
+            Some(preview) => preview,
+            None => return ui.allocate_rect(self.get_board_rect(), Sense::hover()),
+        };
+
+        for y in 0..preview.board.height() {
+            for x in 0..preview.board.width() {
+                let rect = self.get_square_rect((x, y));
+
+                ui.painter()
+                    .rect_filled(rect, Rounding::none(), self.get_square_bg_color(y, x));
+
+                if let Some(ins) = preview.board.get(x, y) {
+                    let piece_color = preview.board.get_color_of_player(&ins.player).clone();
+
+                    self.paint_piece_at(ui, &ins.piece, &piece_color, &rect);
+                }
+            }
+        }
+
+        let color = AnnotationColor::Blue;
+
+        self.paint_arrow(ui, &color, preview.mv.from, preview.mv.to);
+        ui.painter().rect_stroke(
+            self.get_board_rect(),
+            Rounding::none(),
+            Stroke::new(3.0, get_annotation_color32(&color)),
+        );
+
+        let response = ui
+            .allocate_rect(self.get_board_rect(), Sense::click())
+            .on_hover_text("Engine line, click to return to the game");
+
+        if response.clicked() {
+            self.preview = None;
+        }
+
+        response
+    }
+
+    /// Sets the marks of the assistant, replacing the previous ones.
+    pub fn set_assistance(&mut self, assistance: Annotations) {
+        self.assistance = assistance;
+    }
+
+    /// Sets the evaluation shown next to the board, in centipawns from
+    /// white's point of view, see [`crate::engine::MATE_SCORE`] for mates.
+    pub fn set_evaluation(&mut self, evaluation: Option<i32>) {
+        self.evaluation = evaluation;
+    }
+
+    /// Bar right of the board, filled with white from white's side as far as
+    /// white is better.
+    fn paint_evaluation_bar(&self, ui: &mut egui::Ui) {
+        let evaluation = match self.evaluation {
+            Some(evaluation) => evaluation,
+            None => return,
+        };
+
+        let board_rect = self.get_board_rect();
+        let rect = Rect::from_min_size(
+            board_rect.right_top() + egui::vec2(EVALUATION_BAR_GAP, 0.0),
+            egui::vec2(EVALUATION_BAR_WIDTH, board_rect.height()),
+        );
+        // Expected score of white, so the bar moves less once the game is
+        // decided anyway.
+        let share = match engine::mate_in(evaluation) {
+            Some(moves) if moves > 0 => 1.0,
+            Some(_) => 0.0,
+            None => 1.0 / (1.0 + 10f32.powf(-evaluation as f32 / 400.0)),
+        };
+        let white_height = rect.height() * share;
+        let white_rect = match self.flipped {
+            true => Rect::from_min_size(rect.min, egui::vec2(rect.width(), white_height)),
+            false => Rect::from_min_max(pos2(rect.left(), rect.bottom() - white_height), rect.max),
+        };
+
+        ui.painter()
+            .rect_filled(rect, Rounding::none(), Color32::from_gray(40));
+        ui.painter()
+            .rect_filled(white_rect, Rounding::none(), Color32::from_gray(235));
+        ui.allocate_rect(rect, Sense::hover())
+            .on_hover_text(engine::format_score(evaluation));
+    }
+
+    /// Writes the game to the save file, if there is one.
+    pub fn save_game(&self) -> Result<(), String> {
+        let save_path = match &self.save_path {
+            Some(save_path) => save_path,
+            None => return Ok(()),
+        };
+
+        let mut file_content = String::new();
+
+        YamlEmitter::new(&mut file_content)
+            .dump(&serialize_game(&self.game))
+            .map_err(|err| format!("{:?}", err))?;
+
+        fs::write(save_path, file_content).map_err(|err| err.to_string())
+    }
+
+    /// Saves the game after it changed and keeps the error for the UI.
+    fn autosave(&mut self) {
+        self.save_error = self.save_game().err();
+    }
+
+    /// Right click marks a square, right dragging from one square to another
+    /// draws an arrow. Doing the same again removes the annotation.
+    fn handle_annotation_input(&mut self, ui: &egui::Ui) {
+        let (pressed, released, pointer_pos, modifiers) = {
+            let input = ui.input();
+
+            (
+                input.pointer.button_pressed(PointerButton::Secondary),
+                input.pointer.button_released(PointerButton::Secondary),
+                input.pointer.interact_pos(),
+                input.modifiers,
+            )
+        };
+
+        let pointer_square = pointer_pos.and_then(|p| self.get_pos_for_point(p));
+
+        // A right click on the board cancels the premoves instead of
+        // annotating.
+        if pressed && pointer_square.is_some() && self.cancel_premoves() {
+            return;
+        }
+
+        if pressed {
+            self.annotation_start = pointer_square;
+        }
+
+        if !released {
+            return;
+        }
+
+        if let (Some(from), Some(to)) = (self.annotation_start.take(), pointer_square) {
+            let color = get_annotation_color(&modifiers);
+            let annotations = self.game.annotations.get_mut(&self.game.board);
+
+            if from == to {
+                annotations.toggle_square(SquareMark { color, pos: from });
+            } else {
+                annotations.toggle_arrow(Arrow { color, from, to });
+            }
+
+            self.autosave();
+        }
+    }
+
+    fn get_pos_for_point(&self, point: Pos2) -> Option<Pos> {
+        let point = point - self.origin.to_vec2();
+        let x = (point.x / PIECE_SIZE as f32).floor() as i8;
+        let y = (point.y / PIECE_SIZE as f32).floor() as i8;
+
+        let is_on_board = point.x >= 0.0
+            && point.y >= 0.0
+            && x < self.game.board.width()
+            && y < self.game.board.height();
+
+        if !is_on_board {
+            return None;
+        }
+
+        Some(self.to_view_pos((x, y)))
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// Rotates the board by 180 degrees, so the other side is at the bottom.
+    pub fn set_flipped(&mut self, flipped: bool) {
+        self.flipped = flipped;
+    }
+
+    /// Maps a square of the board to where it is drawn and back, since a
+    /// rotation by 180 degrees is its own inverse.
+    fn to_view_pos(&self, (x, y): Pos) -> Pos {
+        match self.flipped {
+            true => (
+                self.game.board.width() - 1 - x,
+                self.game.board.height() - 1 - y,
+            ),
+            false => (x, y),
+        }
+    }
+
+    fn get_square_rect(&self, pos: Pos) -> Rect {
+        let (x, y) = self.to_view_pos(pos);
+
+        get_square_rect_for_pos(x, y).translate(self.origin.to_vec2())
+    }
+
+    fn paint_square_mark(&self, ui: &mut egui::Ui, mark: &SquareMark) {
+        let rect = self.get_square_rect(mark.pos);
+
+        ui.painter().circle_stroke(
+            rect.center(),
+            PIECE_SIZE as f32 / 2.0 - 2.0,
+            Stroke::new(3.0, get_annotation_color32(&mark.color)),
+        );
+    }
+
+    fn paint_arrow(&self, ui: &mut egui::Ui, color: &AnnotationColor, from: Pos, to: Pos) {
+        let origin = self.get_square_rect(from).center();
+        let target = self.get_square_rect(to).center();
+
+        ui.painter().arrow(
+            origin,
+            target - origin,
+            Stroke::new(6.0, get_annotation_color32(color)),
+        );
+    }
+
+    fn paint_annotations(&self, ui: &mut egui::Ui) {
+        if let Some(annotations) = self.game.annotations.get(&self.game.board) {
+            for mark in &annotations.squares {
+                self.paint_square_mark(ui, mark);
+            }
+
+            for arrow in &annotations.arrows {
+                self.paint_arrow(ui, &arrow.color, arrow.from, arrow.to);
+            }
+        }
+
+        for mark in &self.assistance.squares {
+            self.paint_square_mark(ui, mark);
+        }
+
+        for arrow in &self.assistance.arrows {
+            self.paint_arrow(ui, &arrow.color, arrow.from, arrow.to);
+        }
+
+        // Preview of the arrow that is currently being drawn.
+        if let Some(from) = self.annotation_start {
+            let (pointer_pos, modifiers) = {
+                let input = ui.input();
+
+                (input.pointer.interact_pos(), input.modifiers)
+            };
+
+            if let Some(to) = pointer_pos.and_then(|p| self.get_pos_for_point(p)) {
+                if from != to {
+                    self.paint_arrow(ui, &get_annotation_color(&modifiers), from, to);
+                }
+            }
+        }
+    }
+
+    /// Dot in the middle of a square the selected piece can move to.
     fn paint_move_at(&self, ui: &mut egui::Ui, x: i8, y: i8) {
         ui.painter().circle(
-            pos2(
-                ((x as usize) * PIECE_SIZE) as f32 + PIECE_SIZE as f32 / 2.0,
-                ((y as usize) * PIECE_SIZE) as f32 + PIECE_SIZE as f32 / 2.0,
-            ),
-            10.0,
-            Color32::BLUE,
-            Stroke::none(),
+            self.get_square_rect((x, y)).center(),
+            7.0,
+            self.colors.move_marker,
+            Stroke::new(2.0, self.colors.marker_outline),
         )
     }
 
+    /// Ring around a piece the selected piece can take. It is painted below
+    /// the piece, so it has to be wide enough to show around it.
     fn paint_hit_at(&self, ui: &mut egui::Ui, x: i8, y: i8) {
-        ui.painter().circle(
-            pos2(
-                ((x as usize) * PIECE_SIZE) as f32 + PIECE_SIZE as f32 / 2.0,
-                ((y as usize) * PIECE_SIZE) as f32 + PIECE_SIZE as f32 / 2.0,
-            ),
-            10.0,
-            Color32::RED,
-            Stroke::none(),
+        let rect = self.get_square_rect((x, y));
+        let radius = PIECE_SIZE as f32 / 2.0 - 3.0;
+
+        ui.painter().circle_stroke(
+            rect.center(),
+            radius + 1.5,
+            Stroke::new(7.0, self.colors.marker_outline),
+        );
+        ui.painter().circle_stroke(
+            rect.center(),
+            radius + 1.5,
+            Stroke::new(4.0, self.colors.take_marker),
         );
     }
+
+    fn get_square_bg_color(&self, y: i8, x: i8) -> Color32 {
+        let is_even_row = y % 2 == 0;
+        let is_even_column = x % 2 == 0;
+
+        match is_even_row && is_even_column || !is_even_row && !is_even_column {
+            true => self.colors.light_square,
+            false => self.colors.dark_square,
+        }
+    }
+
+    pub fn set_visual_theme(&mut self, theme: VisualTheme) {
+        self.colors = theme.board_colors();
+    }
 }
 
 impl Widget for &mut BoardWidget {
     fn ui(self, ui: &mut eframe::egui::Ui) -> eframe::egui::Response {
-        let info_board = self.board.get_moves_of_selected();
+        self.origin = ui.available_rect_before_wrap().min;
+        self.paint_evaluation_bar(ui);
+
+        if self.is_editing() {
+            return self.editor_board_ui(ui);
+        }
 
-        let promotion_in_progress =
-            if let Some((promote_x, promote_y)) = self.board.get_promote_pos() {
-                let mut selected_piece = None;
+        if self.is_previewing() {
+            return self.preview_board_ui(ui);
+        }
 
-                ui.add(promote_widget(
-                    &mut selected_piece,
-                    self.dynamic_texture_manager.clone(),
-                ));
+        let info_board = self.game.board.get_moves_of_selected();
 
-                if let Some(selected_piece) = selected_piece {
-                    self.board.promote_piece_to(selected_piece);
-                }
+        let input_locked = self.game.is_over();
 
+        let promotion_in_progress = match self.game.board.get_promote_pos() {
+            Some(promote_pos) => {
+                self.promotion_ui(ui, promote_pos);
                 true
-            } else {
-                false
-            };
+            }
+            None => false,
+        };
 
-        for y in 0..self.board.height() {
-            for x in 0..self.board.width() {
-                let bg_color = get_square_bg_color(y, x);
-                let rect = get_square_rect_for_pos(x, y);
+        for y in 0..self.game.board.height() {
+            for x in 0..self.game.board.width() {
+                let bg_color = self.get_square_bg_color(y, x);
+                let rect = self.get_square_rect((x, y));
 
                 ui.painter().rect_filled(rect, Rounding::none(), bg_color);
 
+                if let Some((from, to)) = self.game.last_move() {
+                    if from == (x, y) || to == (x, y) {
+                        self.paint_last_move_at(ui, &rect);
+                    }
+                }
+
+                if self.checked_king_pos == Some((x, y)) {
+                    self.paint_check_at(ui, &rect);
+                }
+
+                if self.is_premove_square((x, y)) {
+                    self.paint_premove_at(ui, &rect);
+                }
+
                 match info_board.get(x, y) {
                     chess_logic::info_board::PosInfo::Move => self.paint_move_at(ui, x, y),
                     chess_logic::info_board::PosInfo::None => (),
+                    chess_logic::info_board::PosInfo::Piece(_) if self.is_animated_to((x, y)) => (),
+                    chess_logic::info_board::PosInfo::PieceHit(_)
+                        if self.is_animated_to((x, y)) =>
+                    {
+                        self.paint_hit_at(ui, x, y)
+                    }
                     chess_logic::info_board::PosInfo::Piece(instance) => {
-                        let piece_color = self.board.get_color_of_player(&instance.player).clone();
+                        let piece_color = self
+                            .game
+                            .board
+                            .get_color_of_player(&instance.player)
+                            .clone();
 
                         self.paint_piece_at(ui, &instance.piece, &piece_color, &rect);
                     }
                     chess_logic::info_board::PosInfo::PieceHit(instance) => {
-                        let piece_color = self.board.get_color_of_player(&instance.player).clone();
+                        let piece_color = self
+                            .game
+                            .board
+                            .get_color_of_player(&instance.player)
+                            .clone();
 
                         self.paint_hit_at(ui, x, y);
                         self.paint_piece_at(ui, &instance.piece, &piece_color, &rect);
@@ -171,53 +1324,57 @@ impl Widget for &mut BoardWidget {
 
                 let square = ui.allocate_rect(rect, Sense::click());
 
-                if !promotion_in_progress && square.clicked() {
-                    if let Some(_) = self.board.get_selected() {
-                        let successfully_moved = self.board.move_selected_to(x, y);
+                if !input_locked && !promotion_in_progress && square.clicked() {
+                    self.activate_square(ui.ctx(), x, y);
+                }
+            }
+        }
 
-                        if !successfully_moved {
-                            self.board.update_selected(x, y);
-                        }
+        self.paint_animation(ui);
+        self.handle_annotation_input(ui);
+        self.paint_annotations(ui);
 
-                        let mut file_content = String::new();
-                        let mut emitter = YamlEmitter::new(&mut file_content);
+        self.paint_result_overlay(ui);
 
-                        emitter.dump(&serialize_game(&self.board)).unwrap();
+        let response = ui.allocate_rect(self.get_board_rect(), Sense::click());
 
-                        fs::write("C:/Users/Elias/Desktop/chess_test.yaml", file_content).unwrap();
-                    } else {
-                        self.board.update_selected(x, y);
-                    }
-                }
+        if response.clicked() {
+            response.request_focus();
+        }
+
+        if response.has_focus() {
+            if !input_locked && !promotion_in_progress {
+                self.handle_keyboard_input(ui);
             }
+
+            self.paint_cursor(ui);
         }
 
-        ui.allocate_rect(
-            Rect::from_two_pos(
-                pos2(0 as f32, 0 as f32),
-                pos2(
-                    (self.board.width() as usize * PIECE_SIZE) as f32,
-                    (self.board.height() as usize * PIECE_SIZE) as f32,
-                ),
-            ),
-            Sense::click(),
-        )
+        response
     }
 }
 
-fn get_square_bg_color(y: i8, x: i8) -> Color32 {
-    const COLOR_BG_BLACK: Color32 = Color32::BROWN;
-    const COLOR_BG_WHITE: Color32 = Color32::WHITE;
-
-    let is_even_row = y % 2 == 0;
-    let is_even_column = x % 2 == 0;
+/// Plain right click draws in green, modifiers select the other colors.
+fn get_annotation_color(modifiers: &Modifiers) -> AnnotationColor {
+    match (modifiers.shift, modifiers.alt || modifiers.ctrl) {
+        (false, false) => AnnotationColor::Green,
+        (true, false) => AnnotationColor::Red,
+        (false, true) => AnnotationColor::Blue,
+        (true, true) => AnnotationColor::Yellow,
+    }
+}
 
-    match is_even_row && is_even_column || !is_even_row && !is_even_column {
-        true => COLOR_BG_WHITE,
-        false => COLOR_BG_BLACK,
+fn get_annotation_color32(color: &AnnotationColor) -> Color32 {
+    match color {
+        AnnotationColor::Blue => Color32::from_rgba_premultiplied(0, 50, 150, 180),
+        AnnotationColor::Green => Color32::from_rgba_premultiplied(0, 120, 30, 180),
+        AnnotationColor::Red => Color32::from_rgba_premultiplied(160, 0, 0, 180),
+        AnnotationColor::Yellow => Color32::from_rgba_premultiplied(170, 140, 0, 180),
     }
 }
 
+/// Rect of the square drawn at `(x, y)`, relative to the top left corner of
+/// the board.
 fn get_square_rect_for_pos(x: i8, y: i8) -> Rect {
     Rect::from_two_pos(
         pos2(
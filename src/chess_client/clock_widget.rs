@@ -0,0 +1,104 @@
+use chess_logic::{Color, Player};
+use eframe::{
+    egui::{self, RichText},
+    epaint::Color32,
+};
+
+use crate::{
+    clock::{format_time, Clock, TimeControl},
+    Game,
+};
+
+/// Time controls offered in the drop down, in the PGN `TimeControl` format.
+const PRESETS: [(&str, &str); 7] = [
+    ("Bullet 1+0", "60"),
+    ("Blitz 3+2", "180+2"),
+    ("Blitz 5+0", "300"),
+    ("Rapid 15+10", "900+10"),
+    ("Rapid 15 Bronstein 10", "900b10"),
+    ("Rapid 25 delay 5", "1500d5"),
+    ("Classical 40/90+30, 30+30", "40/5400+30:1800+30"),
+];
+
+/// Shows the remaining time of both players and lets the user start a clock
+/// with one of the presets or a custom time control.
+pub fn clock_widget<'a>(
+    game: &'a mut Game,
+    time_control_input: &'a mut String,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        let now = ui.input().time;
+
+        ui.vertical(|ui| {
+            if let Some(clock) = &game.clock {
+                for player in [Player::Opponent, Player::You] {
+                    let color = game.board.get_color_of_player(&player).clone();
+
+                    player_time_ui(ui, clock, &player, &color, now);
+                }
+            }
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("time control presets")
+                    .selected_text("Presets")
+                    .show_ui(ui, |ui| {
+                        for (name, control) in PRESETS {
+                            ui.selectable_value(time_control_input, control.to_owned(), name);
+                        }
+                    });
+
+                ui.add(egui::TextEdit::singleline(time_control_input).desired_width(120.0))
+                    .on_hover_text("e.g. 300+2, 900b10, 1500d5 or 40/5400+30:1800+30");
+            });
+
+            ui.horizontal(|ui| match TimeControl::parse(time_control_input) {
+                Some(control) => {
+                    if ui.button("Start clock").clicked() {
+                        let mut clock = Clock::new(control);
+                        clock.start(&game.to_move, now);
+
+                        game.clock = Some(clock);
+                    }
+
+                    if game.clock.is_some() && ui.button("Remove clock").clicked() {
+                        game.clock = None;
+                    }
+                }
+                None => {
+                    ui.colored_label(Color32::RED, "Invalid time control");
+                }
+            });
+        })
+        .response
+    }
+}
+
+fn player_time_ui(ui: &mut egui::Ui, clock: &Clock, player: &Player, color: &Color, now: f64) {
+    let remaining = clock.remaining(player, now);
+    let is_running = match clock.running_player() {
+        Some(running) => running == player,
+        None => false,
+    };
+    let has_flagged = match clock.flagged() {
+        Some(flagged) => flagged == player,
+        None => false,
+    };
+
+    let mut text = RichText::new(format!("{:?}  {}", color, format_time(remaining)))
+        .monospace()
+        .size(20.0);
+
+    if is_running {
+        text = text
+            .strong()
+            .background_color(Color32::from_rgb(60, 120, 60));
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(text);
+
+        if has_flagged {
+            ui.colored_label(Color32::RED, "⚑ Time");
+        }
+    });
+}
@@ -0,0 +1,216 @@
+use chess_logic::{Color, Player};
+use eframe::egui;
+
+use crate::{
+    engine::{self, BackgroundSearch, SearchLimits},
+    notation, rules,
+};
+
+use super::{analysis_widget::AnalysisLine, board_widget::BoardWidget};
+
+/// Depth the analysis stops at, high enough to never be reached.
+const ANALYSIS_DEPTH: u32 = 64;
+const MAX_ANALYSIS_LINES: usize = 5;
+
+/// Computer opponent of a single game, which can also analyse the shown
+/// position.
+pub struct ComputerOpponent {
+    /// Search of the shown position while analysing, together with its FEN.
+    analysis: Option<(BackgroundSearch, String)>,
+    /// Number of best moves the analysis shows.
+    analysis_lines: usize,
+    pub analysing: bool,
+    /// Color the computer plays, `None` if both sides are played by the user.
+    pub color: Option<Color>,
+    pub level: u8,
+    /// Lines of the analysis with their moves in SAN, converted whenever the
+    /// search reports new ones.
+    lines: Vec<AnalysisLine>,
+    /// Running search together with the FEN of the position it searches.
+    search: Option<(BackgroundSearch, String)>,
+}
+
+impl ComputerOpponent {
+    pub fn new() -> Self {
+        Self {
+            analysis: None,
+            analysis_lines: 3,
+            analysing: false,
+            color: None,
+            level: 3,
+            lines: Vec::new(),
+            search: None,
+        }
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Player the computer moves on the board, if any.
+    pub fn player(&self, board: &BoardWidget) -> Option<Player> {
+        let color = self.color.as_ref()?;
+
+        Some(rules::player_of_color(&board.game().board, color))
+    }
+
+    /// Best lines of the analysis of the shown position, the best one first.
+    pub fn analysis_lines(&self) -> &[AnalysisLine] {
+        match self.analysis {
+            Some(_) => &self.lines,
+            None => &[],
+        }
+    }
+
+    /// Starts thinking when it is the computer's turn and plays the move once
+    /// the search is done. Has to be called every frame.
+    pub fn update(&mut self, ctx: &egui::Context, board: &mut BoardWidget) {
+        let player = self.player(board);
+        let game = board.game();
+        // While the user browses earlier moves, a reply would start a
+        // variation.
+        let is_computer_turn = player.as_ref() == Some(&game.to_move) && game.is_at_line_end();
+
+        self.update_analysis(ctx, board, is_computer_turn);
+
+        if !is_computer_turn || game.is_over() || board.is_editing() {
+            self.search = None;
+            return;
+        }
+
+        let fen = notation::format_fen(&game.board, &game.to_move);
+
+        // The position changed while thinking, e.g. because of an undo.
+        if !matches!(&self.search, Some((_, searched)) if *searched == fen) {
+            let search = BackgroundSearch::start(
+                game.board.clone(),
+                game.to_move.clone(),
+                SearchLimits::for_level(self.level),
+            );
+
+            self.search = Some((search, fen));
+        }
+
+        let (search, _) = self.search.as_mut().unwrap();
+
+        search.poll();
+
+        if !search.is_finished() {
+            ctx.request_repaint();
+            return;
+        }
+
+        let mv = search.latest().and_then(|info| info.best_move()).cloned();
+
+        self.search = None;
+
+        if let Some(mv) = mv {
+            board.play_move(ctx, &mv);
+        }
+    }
+
+    /// Analyses the shown position while the user is to move, starting over
+    /// whenever it changes.
+    fn update_analysis(
+        &mut self,
+        ctx: &egui::Context,
+        board: &BoardWidget,
+        is_computer_turn: bool,
+    ) {
+        let game = board.game();
+
+        if !self.analysing || is_computer_turn || game.is_over() || board.is_editing() {
+            self.analysis = None;
+            return;
+        }
+
+        let fen = notation::format_fen(&game.board, &game.to_move);
+
+        if !matches!(&self.analysis, Some((_, analysed)) if *analysed == fen) {
+            let limits = SearchLimits {
+                depth: ANALYSIS_DEPTH,
+                multi_pv: self.analysis_lines,
+                randomness: 0,
+                time: None,
+            };
+            let search = BackgroundSearch::start(game.board.clone(), game.to_move.clone(), limits);
+
+            self.analysis = Some((search, fen));
+            self.lines.clear();
+        }
+
+        let (search, _) = self.analysis.as_mut().unwrap();
+
+        if search.poll() {
+            self.lines = search
+                .lines()
+                .iter()
+                .map(|info| AnalysisLine::new(info.clone(), &game.board, &game.to_move))
+                .collect();
+        }
+
+        if !search.is_finished() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// Lets the user choose the color and the strength of the computer.
+pub fn computer_widget(computer: &mut ComputerOpponent) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            side_ui(ui, "Computer plays", &mut computer.color);
+            ui.add(
+                egui::Slider::new(&mut computer.level, engine::MIN_LEVEL..=engine::MAX_LEVEL)
+                    .text("Strength"),
+            );
+
+            if computer.is_thinking() {
+                ui.label("Thinking…");
+            }
+
+            ui.checkbox(&mut computer.analysing, "Analyse the shown position");
+
+            let lines = egui::Slider::new(&mut computer.analysis_lines, 1..=MAX_ANALYSIS_LINES)
+                .text("Lines");
+
+            // The search only reports as many lines as it was started with.
+            if computer.analysing && ui.add(lines).changed() {
+                computer.analysis = None;
+            }
+        })
+        .response
+    }
+}
+
+/// Combo box to choose the color an engine plays, `None` being neither.
+pub fn side_ui(ui: &mut egui::Ui, label: &str, color: &mut Option<Color>) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+
+        let selected = match color {
+            Some(Color::White) => "White",
+            Some(Color::Black) => "Black",
+            None => "Nobody",
+        };
+
+        egui::ComboBox::from_id_source(label)
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(selected == "Nobody", "Nobody")
+                    .clicked()
+                {
+                    *color = None;
+                }
+
+                if ui.selectable_label(selected == "White", "White").clicked() {
+                    *color = Some(Color::White);
+                }
+
+                if ui.selectable_label(selected == "Black", "Black").clicked() {
+                    *color = Some(Color::Black);
+                }
+            });
+    });
+}
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use egui_extras::dynamic_texture_manager::bytes_loader::BytesLoader;
+
+/// Theme name under which the piece set compiled into the binary is found.
+pub const EMBEDDED_THEME: &str = "embedded";
+
+const EMBEDDED_ASSETS: [(&str, &[u8]); 12] = [
+    (
+        "bishop_black.svg",
+        include_bytes!("../assets/bishop_black.svg"),
+    ),
+    (
+        "bishop_white.svg",
+        include_bytes!("../assets/bishop_white.svg"),
+    ),
+    ("king_black.svg", include_bytes!("../assets/king_black.svg")),
+    ("king_white.svg", include_bytes!("../assets/king_white.svg")),
+    (
+        "knight_black.svg",
+        include_bytes!("../assets/knight_black.svg"),
+    ),
+    (
+        "knight_white.svg",
+        include_bytes!("../assets/knight_white.svg"),
+    ),
+    ("pawn_black.svg", include_bytes!("../assets/pawn_black.svg")),
+    ("pawn_white.svg", include_bytes!("../assets/pawn_white.svg")),
+    (
+        "queen_black.svg",
+        include_bytes!("../assets/queen_black.svg"),
+    ),
+    (
+        "queen_white.svg",
+        include_bytes!("../assets/queen_white.svg"),
+    ),
+    ("rook_black.svg", include_bytes!("../assets/rook_black.svg")),
+    ("rook_white.svg", include_bytes!("../assets/rook_white.svg")),
+];
+
+/// Loads the piece images that are compiled into the artifact, so the client
+/// works regardless of the working directory and on the web.
+///
+/// Paths inside the [`EMBEDDED_THEME`] are served from memory. If an override
+/// directory is set, files with the same name in that directory take
+/// precedence. Any other path is read from the file system (native only).
+#[derive(Default)]
+pub struct EmbeddedBytesLoader {
+    override_dir: Option<PathBuf>,
+}
+
+impl EmbeddedBytesLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_override_dir(override_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            override_dir: Some(override_dir.into()),
+        }
+    }
+}
+
+impl BytesLoader for EmbeddedBytesLoader {
+    fn load(&self, path: &str) -> Result<Vec<u8>, String> {
+        let embedded_name = path
+            .strip_prefix(EMBEDDED_THEME)
+            .map(|name| name.trim_start_matches('/'));
+
+        let embedded_name = match embedded_name {
+            Some(name) => name,
+            None => return read_file(path.into()),
+        };
+
+        if let Some(override_dir) = &self.override_dir {
+            if let Ok(bytes) = read_file(override_dir.join(embedded_name)) {
+                return Ok(bytes);
+            }
+        }
+
+        EMBEDDED_ASSETS
+            .iter()
+            .find(|(name, _)| *name == embedded_name)
+            .map(|(_, bytes)| bytes.to_vec())
+            .ok_or_else(|| format!("no embedded asset named '{}'", embedded_name))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: PathBuf) -> Result<Vec<u8>, String> {
+    std::fs::read(&path).map_err(|err| format!("failed to read '{}': {}", path.display(), err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_file(path: PathBuf) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "cannot read '{}', the file system is not available on the web",
+        path.display()
+    ))
+}
@@ -0,0 +1,454 @@
+use std::{
+    error::Error,
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use chess_logic::{Board, Color, Player};
+use eframe::{egui, epaint::Color32};
+
+use crate::{
+    cecp::CecpEngine,
+    engine::{self, SearchInfo},
+    notation, rules,
+    uci::{self, GoLimits, UciEngine, UciEvent, UciInfo},
+};
+
+use super::{analysis_widget::AnalysisLine, board_widget::BoardWidget, computer_widget};
+
+const MAX_ANALYSIS_LINES: usize = 5;
+
+/// Protocol the engine speaks.
+#[derive(Clone, Copy, PartialEq)]
+enum Protocol {
+    Cecp,
+    Uci,
+}
+
+/// Running engine of either protocol.
+enum Connection {
+    Cecp(CecpEngine),
+    Uci(UciEngine),
+}
+
+impl Connection {
+    fn start(protocol: Protocol, program: &str) -> Result<Self, Box<dyn Error>> {
+        match protocol {
+            Protocol::Cecp => CecpEngine::start(program).map(Connection::Cecp),
+            Protocol::Uci => UciEngine::start(program).map(Connection::Uci),
+        }
+    }
+
+    /// Name of the engine, with the author if it is known.
+    fn title(&self) -> String {
+        match self {
+            Connection::Cecp(engine) => engine.name().to_owned(),
+            Connection::Uci(engine) if engine.author().is_empty() => engine.name().to_owned(),
+            Connection::Uci(engine) => format!("{} by {}", engine.name(), engine.author()),
+        }
+    }
+
+    fn has_exited(&self) -> bool {
+        match self {
+            Connection::Cecp(engine) => engine.has_exited(),
+            Connection::Uci(engine) => engine.has_exited(),
+        }
+    }
+
+    /// Sets how many lines the engine reports. Only UCI engines that have
+    /// the `MultiPV` option can report more than one.
+    fn set_lines(&mut self, lines: usize) -> Result<(), Box<dyn Error>> {
+        match self {
+            Connection::Uci(engine) if has_option(engine, "MultiPV") => {
+                engine.set_option("MultiPV", &lines.to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn set_position(&mut self, fen: &str, moves: &[String]) -> Result<(), Box<dyn Error>> {
+        match self {
+            Connection::Cecp(engine) => engine.set_position(fen, moves),
+            Connection::Uci(engine) => engine.set_position(fen, moves),
+        }
+    }
+
+    fn go(&mut self, limits: &GoLimits) -> Result<(), Box<dyn Error>> {
+        match self {
+            Connection::Cecp(engine) => engine.go(limits),
+            Connection::Uci(engine) => engine.go(limits),
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Connection::Cecp(engine) => engine.stop(),
+            Connection::Uci(engine) => engine.stop(),
+        }
+    }
+
+    fn poll(&mut self) -> Vec<UciEvent> {
+        match self {
+            Connection::Cecp(engine) => engine.poll(),
+            Connection::Uci(engine) => engine.poll(),
+        }
+    }
+}
+
+/// Why the engine is searching.
+#[derive(Clone, Copy, PartialEq)]
+enum Task {
+    /// Find a move to play for its side.
+    Play,
+    /// Analyse the shown position until it changes, reporting this many
+    /// lines.
+    Analyse(usize),
+}
+
+/// External UCI or CECP engine attached to a single game, which can play one
+/// side or analyse the shown position.
+pub struct ExternalEngine {
+    /// Number of best moves the analysis shows.
+    analysis_lines: usize,
+    pub analysing: bool,
+    /// Color the engine plays, `None` if both sides are played by the user.
+    pub color: Option<Color>,
+    engine: Option<Connection>,
+    /// Number of lines the engine was told to report, `None` until it was.
+    engine_lines: Option<usize>,
+    error: Option<String>,
+    /// Latest info of each line of the running search, the main line first,
+    /// converted when it arrives.
+    lines: Vec<AnalysisLine>,
+    /// Seconds the engine gets per move.
+    move_time: f64,
+    path_input: String,
+    protocol: Protocol,
+    /// FEN of the searched position, with the reason for the search.
+    searching: Option<(String, Task)>,
+    /// Connection being set up on another thread, as the handshake can take
+    /// seconds.
+    starting: Option<mpsc::Receiver<Result<Connection, String>>>,
+    /// A search was stopped and its `bestmove` has not arrived yet.
+    stopping: bool,
+}
+
+impl ExternalEngine {
+    pub fn new() -> Self {
+        Self {
+            analysis_lines: 3,
+            analysing: false,
+            color: None,
+            engine: None,
+            engine_lines: None,
+            error: None,
+            lines: Vec::new(),
+            move_time: 2.0,
+            path_input: String::new(),
+            protocol: Protocol::Uci,
+            searching: None,
+            starting: None,
+            stopping: false,
+        }
+    }
+
+    /// Player the engine moves on the board, if any.
+    pub fn player(&self, board: &BoardWidget) -> Option<Player> {
+        let color = self.color.as_ref().filter(|_| self.engine.is_some())?;
+
+        Some(rules::player_of_color(&board.game().board, color))
+    }
+
+    /// Lines of the analysis of the shown position, the best one first.
+    pub fn analysis_lines(&self) -> &[AnalysisLine] {
+        match self.searching {
+            Some((_, Task::Analyse(_))) => &self.lines,
+            _ => &[],
+        }
+    }
+
+    fn start(&mut self) {
+        self.stop();
+
+        let (sender, receiver) = mpsc::channel();
+        let protocol = self.protocol;
+        let program = self.path_input.trim().to_owned();
+
+        thread::spawn(move || {
+            let result = Connection::start(protocol, &program).map_err(|err| err.to_string());
+            let _ = sender.send(result);
+        });
+
+        self.error = None;
+        self.starting = Some(receiver);
+    }
+
+    fn stop(&mut self) {
+        self.engine = None;
+        self.engine_lines = None;
+        self.lines.clear();
+        self.searching = None;
+        self.starting = None;
+        self.stopping = false;
+    }
+
+    /// Reads the output of the engine, plays its move once it is done and
+    /// starts a new search whenever the shown position needs one. Has to be
+    /// called every frame.
+    pub fn update(&mut self, ctx: &egui::Context, board: &mut BoardWidget) {
+        if let Err(err) = self.try_update(ctx, board) {
+            self.stop();
+            self.error = Some(err);
+        }
+    }
+
+    fn try_update(&mut self, ctx: &egui::Context, board: &mut BoardWidget) -> Result<(), String> {
+        if let Some(starting) = &self.starting {
+            match starting.try_recv() {
+                Ok(result) => {
+                    self.engine = Some(result?);
+                    self.engine_lines = None;
+                    self.starting = None;
+                }
+                Err(TryRecvError::Empty) => {
+                    ctx.request_repaint();
+                    return Ok(());
+                }
+                Err(TryRecvError::Disconnected) => {
+                    return Err("The engine failed to start".to_owned())
+                }
+            }
+        }
+
+        let player = self.player(board);
+        let engine = match &mut self.engine {
+            Some(engine) => engine,
+            None => return Ok(()),
+        };
+
+        let game = board.game();
+        let fen = notation::format_fen(&game.board, &game.to_move);
+        let is_searching_shown = matches!(&self.searching, Some((searched, _)) if *searched == fen);
+        let mut best_move = None;
+
+        for event in engine.poll() {
+            match event {
+                // Lines of a stopped search, or of a position that is no
+                // longer shown, can't be replayed on the board.
+                UciEvent::Info(info) if !self.stopping && is_searching_shown => {
+                    let index = info.multipv.unwrap_or(1).max(1) as usize - 1;
+                    let line = match to_analysis_line(&info, &game.board, &game.to_move) {
+                        Some(line) => line,
+                        None => continue,
+                    };
+
+                    if index < self.lines.len() {
+                        self.lines[index] = line;
+                    } else if index == self.lines.len() {
+                        self.lines.push(line);
+                    }
+                }
+                UciEvent::Info(_) => (),
+                UciEvent::BestMove { .. } if self.stopping => self.stopping = false,
+                UciEvent::BestMove { mv, .. } => {
+                    if let Some((searched, Task::Play)) = self.searching.take() {
+                        best_move = mv.filter(|_| searched == fen);
+                    }
+                }
+            }
+        }
+
+        if engine.has_exited() {
+            return Err("The engine exited".to_owned());
+        }
+
+        let best_move =
+            best_move.and_then(|mv| notation::parse_move(&game.board, &game.to_move, &mv));
+
+        if let Some(mv) = best_move {
+            board.play_move(ctx, &mv);
+            return Ok(());
+        }
+
+        let task = match player {
+            _ if game.is_over() || board.is_editing() => None,
+            // While the user browses earlier moves, a reply would start a
+            // variation.
+            Some(player) if player == game.to_move && game.is_at_line_end() => Some(Task::Play),
+            _ if self.analysing => Some(Task::Analyse(self.analysis_lines)),
+            _ => None,
+        };
+        let wanted = task.map(|task| (fen, task));
+
+        // The position changed, so the running search has to end before the
+        // next one can start.
+        if !self.stopping && self.searching != wanted {
+            match wanted {
+                _ if self.searching.is_some() => {
+                    engine.stop().map_err(|err| err.to_string())?;
+                    self.lines.clear();
+                    self.searching = None;
+                    self.stopping = true;
+                }
+                Some((fen, task)) => {
+                    let (start, moves) = uci::game_position(game);
+                    let (limits, lines) = match task {
+                        Task::Play => (
+                            GoLimits::MoveTime(Duration::from_secs_f64(self.move_time)),
+                            1,
+                        ),
+                        Task::Analyse(lines) => (GoLimits::Infinite, lines),
+                    };
+
+                    // Changing an option can make the engine reinitialize,
+                    // so it is only sent when needed.
+                    if self.engine_lines != Some(lines) {
+                        engine.set_lines(lines).map_err(|err| err.to_string())?;
+                        self.engine_lines = Some(lines);
+                    }
+
+                    engine
+                        .set_position(&start, &moves)
+                        .and_then(|_| engine.go(&limits))
+                        .map_err(|err| err.to_string())?;
+                    self.lines.clear();
+                    self.searching = Some((fen, task));
+                }
+                None => (),
+            }
+        }
+
+        if self.searching.is_some() || self.stopping {
+            ctx.request_repaint();
+        }
+
+        Ok(())
+    }
+}
+
+/// Lets the user start an engine, choose what it does and shows its current
+/// evaluation and best line.
+pub fn engine_widget(engine: &mut ExternalEngine) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Engine");
+                let is_stopped = engine.engine.is_none() && engine.starting.is_none();
+
+                ui.add_enabled_ui(is_stopped, |ui| {
+                    ui.radio_value(&mut engine.protocol, Protocol::Uci, "UCI");
+                    ui.radio_value(&mut engine.protocol, Protocol::Cecp, "CECP")
+                        .on_hover_text("Chess Engine Communication Protocol (xboard/WinBoard)");
+                });
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut engine.path_input).desired_width(120.0))
+                    .on_hover_text("Path to the engine executable");
+
+                if engine.engine.is_some() || engine.starting.is_some() {
+                    if ui.button("Stop").clicked() {
+                        engine.stop();
+                    }
+                } else if ui.button("Start").clicked() {
+                    engine.start();
+                }
+            });
+
+            if let Some(err) = &engine.error {
+                ui.colored_label(Color32::RED, err);
+            }
+
+            if engine.starting.is_some() {
+                ui.label("Starting…");
+            }
+
+            match &engine.engine {
+                Some(connection) => ui.label(connection.title()),
+                None => return,
+            };
+
+            computer_widget::side_ui(ui, "Engine plays", &mut engine.color);
+            ui.add(
+                egui::Slider::new(&mut engine.move_time, 0.1..=30.0)
+                    .logarithmic(true)
+                    .suffix(" s")
+                    .text("Time per move"),
+            );
+            ui.checkbox(&mut engine.analysing, "Analyse the shown position");
+
+            if engine.analysing {
+                ui.add(
+                    egui::Slider::new(&mut engine.analysis_lines, 1..=MAX_ANALYSIS_LINES)
+                        .text("Lines"),
+                );
+            }
+
+            if let Some(line) = engine.lines.first() {
+                ui.label(format_info(line));
+            }
+        })
+        .response
+    }
+}
+
+fn has_option(engine: &UciEngine, name: &str) -> bool {
+    engine
+        .options()
+        .iter()
+        .any(|option| option.name.eq_ignore_ascii_case(name))
+}
+
+/// Converts the info into a line starting in the given position, with the
+/// moves as far as they can be replayed. `None` without a score or a move.
+fn to_analysis_line(info: &UciInfo, board: &Board, player: &Player) -> Option<AnalysisLine> {
+    let mut position = board.clone();
+    let mut player_to_move = player.clone();
+    let mut pv = Vec::new();
+
+    // CECP engines usually send SAN, UCI engines always send UCI.
+    for text_move in &info.pv {
+        let mv = notation::parse_move(&position, &player_to_move, text_move);
+        let after = mv.and_then(|mv| rules::apply_move(&position, &mv));
+
+        match (mv, after) {
+            (Some(mv), Some(after)) => {
+                pv.push(mv);
+                position = after;
+                player_to_move = rules::opponent_of(&player_to_move);
+            }
+            _ => break,
+        }
+    }
+
+    if pv.is_empty() {
+        return None;
+    }
+
+    let info = SearchInfo {
+        depth: info.depth.unwrap_or(0),
+        nodes: info.nodes.unwrap_or(0),
+        pv,
+        score: info.score.as_ref()?.to_engine_score(),
+    };
+
+    Some(AnalysisLine::new(info, board, player))
+}
+
+/// Formats the line like `"+0.31 (depth 12) e4 e5 Nf3"`.
+fn format_info(line: &AnalysisLine) -> String {
+    let mut text = engine::format_score(line.info.score);
+
+    // Engines that don't report the depth leave it at 0.
+    if line.info.depth > 0 {
+        text.push_str(&format!(" (depth {})", line.info.depth));
+    }
+
+    for san in &line.san {
+        text.push(' ');
+        text.push_str(san);
+    }
+
+    text
+}
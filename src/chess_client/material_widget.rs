@@ -0,0 +1,55 @@
+use std::sync::{Arc, Mutex};
+
+use chess_logic::Player;
+use eframe::egui;
+
+use crate::{material, rules, Game};
+
+use super::piece_textures::PieceTextures;
+
+const CAPTURED_PIECE_TEXTURE_SIZE: (usize, usize) = (20, 20);
+const CAPTURED_PIECE_SIZE: egui::Vec2 = egui::vec2(
+    CAPTURED_PIECE_TEXTURE_SIZE.0 as f32,
+    CAPTURED_PIECE_TEXTURE_SIZE.1 as f32,
+);
+
+/// Shows the pieces each player captured and their material advantage, e.g.
+/// "+3".
+pub fn material_widget(
+    game: &Game,
+    piece_textures: Arc<Mutex<PieceTextures>>,
+) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            for player in [Player::Opponent, Player::You] {
+                let captured = material::captured_by(&game.history, &player);
+                let balance = material::material_balance(&game.board, &player);
+
+                // The captured pieces are drawn in the color of their owner.
+                let captured_color = game
+                    .board
+                    .get_color_of_player(&rules::opponent_of(&player))
+                    .clone();
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{:?}:", game.board.get_color_of_player(&player)));
+
+                    for piece in &captured {
+                        let texture_id = piece_textures.lock().unwrap().get(
+                            piece,
+                            &captured_color,
+                            &CAPTURED_PIECE_TEXTURE_SIZE,
+                        );
+
+                        ui.image(texture_id, CAPTURED_PIECE_SIZE);
+                    }
+
+                    if balance > 0 {
+                        ui.label(format!("+{}", balance));
+                    }
+                });
+            }
+        })
+        .response
+    }
+}
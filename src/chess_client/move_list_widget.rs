@@ -0,0 +1,139 @@
+use chess_logic::Color;
+use eframe::egui;
+
+use crate::{
+    history::{History, NodeId},
+    pgn,
+};
+
+/// What the user did in the move list.
+pub enum MoveListAction {
+    /// Show the position after the node, `None` for the starting position.
+    GoTo(Option<NodeId>),
+    Promote(NodeId),
+    Demote(NodeId),
+    /// Delete the move and everything after it.
+    Delete(NodeId),
+}
+
+/// Lists the moves of the history like PGN movetext (`1. e4 e5 (1... c5)`),
+/// with side variations in parentheses after the move they replace. The
+/// current move is highlighted, clicking a move shows it and its context
+/// menu changes the order of the variations.
+pub fn move_list_widget<'a>(
+    history: &'a History,
+    action: &'a mut Option<MoveListAction>,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            if ui
+                .selectable_label(history.current() == 0, "Start")
+                .clicked()
+            {
+                *action = Some(MoveListAction::GoTo(None));
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    if let Some(first) = history.children(None).first() {
+                        line_ui(ui, history, *first, 0, action);
+                    }
+                });
+            });
+        })
+        .response
+    }
+}
+
+/// Moves from `id` to the end of its line, including the side variations
+/// branching off it. `level` is the nesting depth of the line.
+fn line_ui(
+    ui: &mut egui::Ui,
+    history: &History,
+    first: NodeId,
+    level: usize,
+    action: &mut Option<MoveListAction>,
+) {
+    let mut id = first;
+    let mut show_number = true;
+
+    loop {
+        move_ui(ui, history, id, show_number, level, action);
+        show_number = false;
+
+        let siblings = history.children(history.parent(id));
+
+        if siblings.first() == Some(&id) && siblings.len() > 1 {
+            for variation in &siblings[1..] {
+                ui.label("(");
+                line_ui(ui, history, *variation, level + 1, action);
+                ui.label(")");
+            }
+
+            show_number = true;
+        }
+
+        match history.children(Some(id)).first() {
+            Some(next) => id = *next,
+            None => break,
+        }
+    }
+}
+
+fn move_ui(
+    ui: &mut egui::Ui,
+    history: &History,
+    id: NodeId,
+    show_number: bool,
+    level: usize,
+    action: &mut Option<MoveListAction>,
+) {
+    let ply = history.ply(id);
+    let is_white = matches!(
+        history.start().get_color_of_player(&ply.player),
+        Color::White
+    );
+
+    if is_white || show_number {
+        let number = pgn::get_move_number(history, id);
+
+        ui.label(match is_white {
+            true => format!("{}.", number),
+            false => format!("{}...", number),
+        });
+    }
+
+    let symbol = ply.nag.and_then(pgn::nag_symbol).unwrap_or("");
+    let mut text = egui::RichText::new(format!("{}{}", ply.san, symbol));
+
+    if level > 0 {
+        text = text.italics();
+    }
+
+    let mut response = ui.selectable_label(history.current_node() == Some(id), text);
+
+    if let Some(comment) = &ply.comment {
+        response = response.on_hover_text(comment);
+    }
+
+    if response.clicked() {
+        *action = Some(MoveListAction::GoTo(Some(id)));
+    }
+
+    response.context_menu(|ui| {
+        if ui.button("Promote variation").clicked() {
+            *action = Some(MoveListAction::Promote(id));
+            ui.close_menu();
+        }
+
+        if ui.button("Demote variation").clicked() {
+            *action = Some(MoveListAction::Demote(id));
+            ui.close_menu();
+        }
+
+        if ui.button("Delete from here").clicked() {
+            *action = Some(MoveListAction::Delete(id));
+            ui.close_menu();
+        }
+    });
+}
@@ -0,0 +1,120 @@
+use chess_logic::{Board, Color, Piece};
+use eframe::egui;
+
+use crate::rules::{is_same_color, is_same_piece, Pos};
+
+/// Default time a piece takes to slide to its new square, in seconds.
+pub const DEFAULT_ANIMATION_DURATION: f32 = 0.2;
+
+pub struct MovingPiece {
+    pub color: Color,
+    pub from: Pos,
+    pub piece: Piece,
+    pub to: Pos,
+}
+
+pub struct FadingPiece {
+    pub color: Color,
+    pub piece: Piece,
+    pub pos: Pos,
+}
+
+/// Transition between two positions. Which pieces move is derived by
+/// comparing the boards, so castling (king and rook), en passant and
+/// promotions are animated without knowing the move itself.
+pub struct PieceAnimation {
+    pub fading: Vec<FadingPiece>,
+    id: egui::Id,
+    pub moving: Vec<MovingPiece>,
+}
+
+impl PieceAnimation {
+    pub fn between(before: &Board, after: &Board, id: egui::Id) -> Self {
+        let mut vanished = Vec::new();
+        let mut appeared = Vec::new();
+
+        for y in 0..after.height() {
+            for x in 0..after.width() {
+                let old = before
+                    .get(x, y)
+                    .map(|ins| (ins.piece, before.get_color_of_player(&ins.player).clone()));
+                let new = after
+                    .get(x, y)
+                    .map(|ins| (ins.piece, after.get_color_of_player(&ins.player).clone()));
+
+                if is_same_content(&old, &new) {
+                    continue;
+                }
+
+                if let Some((piece, color)) = old {
+                    vanished.push(FadingPiece {
+                        color,
+                        piece,
+                        pos: (x, y),
+                    });
+                }
+
+                if let Some((piece, color)) = new {
+                    appeared.push(MovingPiece {
+                        color,
+                        from: (x, y),
+                        piece,
+                        to: (x, y),
+                    });
+                }
+            }
+        }
+
+        // Every piece that appeared came from a square of the same color.
+        // Prefer a piece of the same kind, anything else is a promotion.
+        for moving in &mut appeared {
+            let origin = vanished
+                .iter()
+                .position(|v| {
+                    is_same_color(&v.color, &moving.color) && is_same_piece(&v.piece, &moving.piece)
+                })
+                .or_else(|| {
+                    vanished
+                        .iter()
+                        .position(|v| is_same_color(&v.color, &moving.color))
+                });
+
+            if let Some(index) = origin {
+                moving.from = vanished.remove(index).pos;
+            }
+        }
+
+        Self {
+            fading: vanished,
+            id,
+            moving: appeared,
+        }
+    }
+
+    /// Returns the progress of the animation from `0.0` to `1.0`.
+    pub fn progress(&self, ctx: &egui::Context, duration: f32) -> f32 {
+        ctx.animate_bool_with_time(self.id, true, duration)
+    }
+
+    /// Registers the animation with egui, so it starts at `0.0`. Has to be
+    /// called once before [`Self::progress`].
+    pub fn start(&self, ctx: &egui::Context) {
+        ctx.animate_bool_with_time(self.id, false, 0.0);
+    }
+
+    /// Checks whether a moving piece ends on the square, which means it must
+    /// not be drawn there until the animation is finished.
+    pub fn is_destination(&self, pos: Pos) -> bool {
+        self.moving.iter().any(|m| m.to == pos)
+    }
+}
+
+fn is_same_content(a: &Option<(Piece, Color)>, b: &Option<(Piece, Color)>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some((a_piece, a_color)), Some((b_piece, b_color))) => {
+            is_same_piece(a_piece, b_piece) && is_same_color(a_color, b_color)
+        }
+        _ => false,
+    }
+}
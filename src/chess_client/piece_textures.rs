@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chess_logic::{Color, Piece};
+use eframe::epaint::TextureId;
+use egui_extras::dynamic_texture_manager::TextureSize;
+
+use super::embedded_bytes_loader::EMBEDDED_THEME;
+
+/// Piece set that is used unless another one is selected.
+pub const DEFAULT_THEME: &str = EMBEDDED_THEME;
+
+const ALL_PIECES: [Piece; 6] = [
+    Piece::Bishop,
+    Piece::King,
+    Piece::Knight,
+    Piece::Pawn,
+    Piece::Queen,
+    Piece::Rook,
+];
+const ALL_COLORS: [Color; 2] = [Color::Black, Color::White];
+
+/// Single place that knows where the image of every piece lives.
+///
+/// Textures are addressed by a key (for the standard pieces `"<piece>_<color>"`,
+/// e.g. `"knight_white"`), which is mapped to a file name inside the current
+/// theme directory. Additional piece kinds can be added with [`Self::register`].
+pub struct PieceTextures {
+    cache: HashMap<(String, TextureSize), TextureId>,
+    dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>,
+    sources: HashMap<String, String>,
+    theme: String,
+}
+
+impl PieceTextures {
+    pub fn new(dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>) -> Self {
+        let mut textures = Self {
+            cache: HashMap::new(),
+            dynamic_texture_manager,
+            sources: HashMap::new(),
+            theme: DEFAULT_THEME.to_owned(),
+        };
+
+        for piece in &ALL_PIECES {
+            for color in &ALL_COLORS {
+                let key = texture_key(piece, color);
+                let file_name = format!("{}.svg", key);
+
+                textures.register(key, file_name);
+            }
+        }
+
+        textures
+    }
+
+    /// Registers (or replaces) the file that is used for the given key.
+    ///
+    /// The file name is relative to the theme directory.
+    pub fn register(&mut self, key: impl Into<String>, file_name: impl Into<String>) {
+        let key = key.into();
+
+        self.cache.retain(|(cached_key, _), _| cached_key != &key);
+        self.sources.insert(key, file_name.into());
+    }
+
+    pub fn theme(&self) -> &str {
+        &self.theme
+    }
+
+    /// Switches to another piece set. Already loaded textures are discarded.
+    pub fn set_theme(&mut self, theme: impl Into<String>) {
+        self.theme = theme.into();
+        self.cache.clear();
+    }
+
+    /// Loads every registered texture in the given sizes, so that the first
+    /// frame does not have to.
+    pub fn preload(&mut self, sizes: &[TextureSize]) {
+        let keys: Vec<String> = self.sources.keys().cloned().collect();
+
+        for key in keys {
+            for size in sizes {
+                self.get_by_key(&key, size);
+            }
+        }
+    }
+
+    pub fn get(&mut self, piece: &Piece, color: &Color, size: &TextureSize) -> TextureId {
+        self.get_by_key(&texture_key(piece, color), size)
+    }
+
+    pub fn get_by_key(&mut self, key: &str, size: &TextureSize) -> TextureId {
+        let cache_key = (key.to_owned(), *size);
+
+        if let Some(texture_id) = self.cache.get(&cache_key) {
+            return *texture_id;
+        }
+
+        let file_name = self
+            .sources
+            .get(key)
+            .unwrap_or_else(|| panic!("no piece texture registered for '{}'", key));
+        let path = format!("{}/{}", self.theme, file_name);
+
+        let texture_id = self
+            .dynamic_texture_manager
+            .lock()
+            .unwrap()
+            .load_sized(&path, size);
+
+        self.cache.insert(cache_key, texture_id);
+
+        texture_id
+    }
+}
+
+/// Key of the texture of a standard piece, e.g. `"queen_black"`.
+pub fn texture_key(piece: &Piece, color: &Color) -> String {
+    format!("{:?}_{:?}", piece, color).to_lowercase()
+}
@@ -0,0 +1,365 @@
+use std::sync::{Arc, Mutex};
+
+use chess_logic::{board::PieceInstance, Board, Color, Piece, Player};
+use eframe::egui;
+
+use crate::{
+    notation,
+    rules::{self, Pos},
+};
+
+use super::{piece_textures::PieceTextures, piece_widget::PieceWidget};
+
+const PALETTE_PIECES: [Piece; 6] = [
+    Piece::King,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+/// What a click on the board does while editing.
+#[derive(Clone)]
+pub enum Tool {
+    Erase,
+    Place(Piece, Color),
+}
+
+/// What the editor asks its owner to do after [`PositionEditor::ui`].
+pub enum EditorAction {
+    Cancel,
+    Start(Board, Player),
+}
+
+/// Castling availability, as in the third field of a FEN.
+#[derive(Clone, Copy)]
+pub struct CastlingRights {
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+}
+
+impl CastlingRights {
+    /// Rights the board still has, like in the FEN of the board.
+    pub fn of_board(board: &Board) -> Self {
+        let castling = notation::get_fen_castling(board);
+
+        Self {
+            black_king_side: castling.contains('k'),
+            black_queen_side: castling.contains('q'),
+            white_king_side: castling.contains('K'),
+            white_queen_side: castling.contains('Q'),
+        }
+    }
+}
+
+/// State of the position setup mode of the board.
+pub struct PositionEditor {
+    pub board: Board,
+    castling: CastlingRights,
+    en_passant_file: Option<i8>,
+    to_move: Color,
+    tool: Tool,
+}
+
+impl PositionEditor {
+    pub fn new(board: Board, to_move: &Player) -> Self {
+        let to_move = board.get_color_of_player(to_move).clone();
+
+        Self {
+            castling: CastlingRights::of_board(&board),
+            board,
+            en_passant_file: None,
+            to_move,
+            tool: Tool::Erase,
+        }
+    }
+
+    /// Applies the selected tool to the square.
+    pub fn click(&mut self, (x, y): Pos) {
+        let ins = match &self.tool {
+            Tool::Erase => None,
+            Tool::Place(piece, color) => {
+                let player = rules::player_of_color(&self.board, color);
+
+                Some(PieceInstance::new(player, *piece))
+            }
+        };
+
+        self.board.set(x, y, ins);
+    }
+
+    /// Removes the piece on the square, regardless of the selected tool.
+    pub fn erase(&mut self, (x, y): Pos) {
+        self.board.set(x, y, None);
+    }
+
+    /// Palette and settings of the editor.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        piece_textures: Arc<Mutex<PieceTextures>>,
+    ) -> Option<EditorAction> {
+        let mut action = None;
+
+        for color in [Color::White, Color::Black] {
+            ui.horizontal(|ui| {
+                for piece in PALETTE_PIECES {
+                    let is_selected = match &self.tool {
+                        Tool::Place(p, c) => {
+                            rules::is_same_piece(p, &piece) && rules::is_same_color(c, &color)
+                        }
+                        Tool::Erase => false,
+                    };
+
+                    let response = ui.add(PieceWidget::new(
+                        piece,
+                        color.clone(),
+                        piece_textures.clone(),
+                    ));
+
+                    if is_selected {
+                        ui.painter().rect_stroke(
+                            response.rect,
+                            egui::Rounding::none(),
+                            ui.visuals().selection.stroke,
+                        );
+                    }
+
+                    if response.clicked() {
+                        self.tool = Tool::Place(piece, color.clone());
+                    }
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .selectable_label(matches!(self.tool, Tool::Erase), "🗑 Erase")
+                .clicked()
+            {
+                self.tool = Tool::Erase;
+            }
+
+            if ui.button("Clear").clicked() {
+                let (you, opponent) = get_colors(&self.board);
+
+                self.board = Board::new(you, opponent);
+            }
+
+            if ui.button("Standard position").clicked() {
+                let (you, opponent) = get_colors(&self.board);
+
+                self.board = Board::new_with_standard_formation(you, opponent);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("To move:");
+            for color in [Color::White, Color::Black] {
+                let is_selected = rules::is_same_color(&self.to_move, &color);
+
+                if ui.radio(is_selected, format!("{:?}", color)).clicked() {
+                    self.to_move = color;
+                    self.en_passant_file = None;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Castling:");
+            ui.checkbox(&mut self.castling.white_king_side, "White O-O");
+            ui.checkbox(&mut self.castling.white_queen_side, "White O-O-O");
+            ui.checkbox(&mut self.castling.black_king_side, "Black O-O");
+            ui.checkbox(&mut self.castling.black_queen_side, "Black O-O-O");
+        });
+
+        ui.horizontal(|ui| {
+            let selected_text = match self.en_passant_file {
+                Some(file) => self.get_en_passant_square_name(file),
+                None => "-".to_owned(),
+            };
+
+            ui.label("En passant:");
+            egui::ComboBox::from_id_source("en passant square")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.en_passant_file, None, "-");
+
+                    for file in self.get_en_passant_candidates() {
+                        let name = self.get_en_passant_square_name(file);
+
+                        ui.selectable_value(&mut self.en_passant_file, Some(file), name);
+                    }
+                });
+        });
+
+        let error = self.get_position_error();
+
+        if let Some(error) = error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(error.is_none(), egui::Button::new("▶ Start from here"))
+                .clicked()
+            {
+                let player = rules::player_of_color(&self.board, &self.to_move);
+
+                action = Some(EditorAction::Start(self.build_board(), player));
+            }
+
+            if ui.button("Cancel").clicked() {
+                action = Some(EditorAction::Cancel);
+            }
+        });
+
+        action
+    }
+
+    /// Why the position can't be played, `None` if it can.
+    fn get_position_error(&self) -> Option<&'static str> {
+        let board = &self.board;
+        let mut kings = [0, 0];
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let ins = match board.get(x, y) {
+                    Some(ins) => ins,
+                    None => continue,
+                };
+
+                match ins.piece {
+                    Piece::King if ins.player == Player::You => kings[0] += 1,
+                    Piece::King => kings[1] += 1,
+                    Piece::Pawn if y == 0 || y == board.height() - 1 => {
+                        return Some("Pawns can't stand on the first or last rank.")
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        if kings != [1, 1] {
+            return Some("Both sides need exactly one king.");
+        }
+
+        let waiting = rules::opponent_of(&rules::player_of_color(board, &self.to_move));
+
+        if rules::is_in_check(board, &waiting) {
+            return Some("The side not to move can't be in check.");
+        }
+
+        None
+    }
+
+    /// Returns the board with the castling rights and en passant square
+    /// applied to the flags of the pieces.
+    fn build_board(&self) -> Board {
+        let mut board = self.board.clone();
+        let height = board.height();
+
+        for y in 0..height {
+            for x in 0..board.width() {
+                let mut ins = match board.get(x, y) {
+                    Some(ins) => ins.clone(),
+                    None => continue,
+                };
+
+                let color = board.get_color_of_player(&ins.player).clone();
+                let square = notation::square_name(&board, (x, y));
+                let (king_side, queen_side) = match color {
+                    Color::White => (
+                        self.castling.white_king_side,
+                        self.castling.white_queen_side,
+                    ),
+                    Color::Black => (
+                        self.castling.black_king_side,
+                        self.castling.black_queen_side,
+                    ),
+                };
+                let home_rank = match color {
+                    Color::White => '1',
+                    Color::Black => '8',
+                };
+                let is_on_home_rank = square.ends_with(home_rank);
+
+                ins.was_moved = match ins.piece {
+                    Piece::King => !(is_on_home_rank && (king_side || queen_side)),
+                    Piece::Rook if is_on_home_rank && square.starts_with('h') => !king_side,
+                    Piece::Rook if is_on_home_rank && square.starts_with('a') => !queen_side,
+                    Piece::Pawn => !square.ends_with(match color {
+                        Color::White => '2',
+                        Color::Black => '7',
+                    }),
+                    _ => true,
+                };
+                ins.is_eligible_for_en_passant =
+                    Some(square.as_str()) == self.get_en_passant_pawn_square().as_deref();
+
+                board.set(x, y, Some(ins));
+            }
+        }
+
+        board
+    }
+
+    /// Files on which a pawn of the side not to move could just have made a
+    /// double step.
+    fn get_en_passant_candidates(&self) -> Vec<i8> {
+        let rank = match self.to_move {
+            Color::White => '5',
+            Color::Black => '4',
+        };
+
+        (0..8)
+            .filter(|file| {
+                let name = format!("{}{}", (b'a' + *file as u8) as char, rank);
+
+                match notation::parse_square(&self.board, &name)
+                    .and_then(|(x, y)| self.board.get(x, y))
+                {
+                    Some(ins) => {
+                        matches!(ins.piece, Piece::Pawn)
+                            && !rules::is_same_color(
+                                self.board.get_color_of_player(&ins.player),
+                                &self.to_move,
+                            )
+                    }
+                    None => false,
+                }
+            })
+            .collect()
+    }
+
+    /// Square behind the pawn that may be captured en passant, e.g. `"e3"`.
+    fn get_en_passant_square_name(&self, file: i8) -> String {
+        let rank = match self.to_move {
+            Color::White => '6',
+            Color::Black => '3',
+        };
+
+        format!("{}{}", (b'a' + file as u8) as char, rank)
+    }
+
+    /// Square of the pawn that may be captured en passant, e.g. `"e4"`.
+    fn get_en_passant_pawn_square(&self) -> Option<String> {
+        let rank = match self.to_move {
+            Color::White => '5',
+            Color::Black => '4',
+        };
+
+        self.en_passant_file
+            .map(|file| format!("{}{}", (b'a' + file as u8) as char, rank))
+    }
+}
+
+fn get_colors(board: &Board) -> (Color, Color) {
+    (
+        board.get_color_of_player(&Player::You).clone(),
+        board.get_color_of_player(&Player::Opponent).clone(),
+    )
+}
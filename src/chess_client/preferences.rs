@@ -0,0 +1,245 @@
+use std::error::Error;
+
+use eframe::egui;
+use linked_hash_map::LinkedHashMap;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
+
+use super::{
+    piece_animation::DEFAULT_ANIMATION_DURATION, piece_textures::DEFAULT_THEME,
+    sound::DEFAULT_SOUND_VOLUME, theme::VisualTheme,
+};
+
+/// Key of the preferences in the eframe storage.
+pub const PREFERENCES_KEY: &str = "preferences";
+/// Key of the open games in the eframe storage.
+pub const GAMES_KEY: &str = "games";
+/// Key of the index of the selected tab in the eframe storage.
+pub const ACTIVE_TAB_KEY: &str = "active_tab";
+
+/// Settings that are kept across restarts, natively in the eframe storage
+/// file and on the web in the local storage.
+///
+/// The sizes of panels and windows are stored by eframe itself, as part of
+/// the egui memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preferences {
+    /// `0.0` disables the animation.
+    pub animation_duration: f32,
+    pub auto_queen: bool,
+    pub flipped: bool,
+    pub piece_set: String,
+    pub premoves_enabled: bool,
+    pub show_side_panel: bool,
+    /// `0.0` mutes the sounds.
+    pub sound_volume: f32,
+    pub visual_theme: VisualTheme,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            animation_duration: DEFAULT_ANIMATION_DURATION,
+            auto_queen: false,
+            flipped: false,
+            piece_set: DEFAULT_THEME.to_owned(),
+            premoves_enabled: true,
+            show_side_panel: true,
+            sound_volume: DEFAULT_SOUND_VOLUME,
+            visual_theme: VisualTheme::Light,
+        }
+    }
+}
+
+impl Preferences {
+    pub fn serialize(&self) -> String {
+        let mut ser = LinkedHashMap::new();
+
+        ser.insert(
+            Yaml::String("animation_duration".to_owned()),
+            Yaml::Real(self.animation_duration.to_string()),
+        );
+        ser.insert(
+            Yaml::String("auto_queen".to_owned()),
+            Yaml::Boolean(self.auto_queen),
+        );
+        ser.insert(
+            Yaml::String("flipped".to_owned()),
+            Yaml::Boolean(self.flipped),
+        );
+        ser.insert(
+            Yaml::String("piece_set".to_owned()),
+            Yaml::String(self.piece_set.clone()),
+        );
+        ser.insert(
+            Yaml::String("premoves_enabled".to_owned()),
+            Yaml::Boolean(self.premoves_enabled),
+        );
+        ser.insert(
+            Yaml::String("show_side_panel".to_owned()),
+            Yaml::Boolean(self.show_side_panel),
+        );
+        ser.insert(
+            Yaml::String("sound_volume".to_owned()),
+            Yaml::Real(self.sound_volume.to_string()),
+        );
+        ser.insert(
+            Yaml::String("visual_theme".to_owned()),
+            Yaml::String(format!("{:?}", self.visual_theme)),
+        );
+
+        let mut content = String::new();
+
+        YamlEmitter::new(&mut content)
+            .dump(&Yaml::Hash(ser))
+            .unwrap();
+
+        content
+    }
+
+    /// Inverse of [`Self::serialize`]. Missing keys keep their default, so
+    /// preferences saved by older versions still load.
+    pub fn deserialize(content: &str) -> Result<Self, Box<dyn Error>> {
+        let docs = YamlLoader::load_from_str(content)?;
+        let ser = docs
+            .first()
+            .ok_or("failed to deserialize empty preferences")?;
+        let mut preferences = Self::default();
+
+        if let Some(duration) = ser["animation_duration"].as_f64() {
+            preferences.animation_duration = duration as f32;
+        }
+        if let Some(auto_queen) = ser["auto_queen"].as_bool() {
+            preferences.auto_queen = auto_queen;
+        }
+        if let Some(flipped) = ser["flipped"].as_bool() {
+            preferences.flipped = flipped;
+        }
+        if let Some(piece_set) = ser["piece_set"].as_str() {
+            preferences.piece_set = piece_set.to_owned();
+        }
+        if let Some(premoves_enabled) = ser["premoves_enabled"].as_bool() {
+            preferences.premoves_enabled = premoves_enabled;
+        }
+        if let Some(show_side_panel) = ser["show_side_panel"].as_bool() {
+            preferences.show_side_panel = show_side_panel;
+        }
+        if let Some(volume) = ser["sound_volume"].as_f64() {
+            preferences.sound_volume = volume as f32;
+        }
+        if let Some(val) = ser["visual_theme"].as_str() {
+            preferences.visual_theme = match val {
+                "Dark" => VisualTheme::Dark,
+                "HighContrast" => VisualTheme::HighContrast,
+                "Light" => VisualTheme::Light,
+                val => {
+                    return Err(
+                        format!("failed to deserialize visual theme with value '{}'", val).into(),
+                    )
+                }
+            };
+        }
+
+        Ok(preferences)
+    }
+}
+
+/// Window to edit the preferences. The piece set is typed into
+/// `piece_set_input` and only applied once it is entered, as loading a set
+/// for every typed letter would look for directories that don't exist.
+pub fn preferences_window(
+    ctx: &egui::Context,
+    open: &mut bool,
+    preferences: &mut Preferences,
+    piece_set_input: &mut String,
+) {
+    egui::Window::new("Settings")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("preferences grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Theme");
+                    ui.horizontal(|ui| {
+                        for theme in VisualTheme::ALL {
+                            ui.radio_value(&mut preferences.visual_theme, theme, theme.name());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Board");
+                    ui.checkbox(&mut preferences.flipped, "Flipped");
+                    ui.end_row();
+
+                    ui.label("Piece set");
+                    let piece_set = ui
+                        .text_edit_singleline(piece_set_input)
+                        .on_hover_text("Name of the directory containing the piece SVGs");
+
+                    if piece_set.lost_focus() && !piece_set_input.trim().is_empty() {
+                        preferences.piece_set = piece_set_input.trim().to_owned();
+                    }
+                    ui.end_row();
+
+                    let mut animate = preferences.animation_duration > 0.0;
+
+                    ui.label("Animation");
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut animate, "Enabled").changed() {
+                            preferences.animation_duration = match animate {
+                                true => DEFAULT_ANIMATION_DURATION,
+                                false => 0.0,
+                            };
+                        }
+
+                        // A disabled slider would still clamp the duration
+                        // into its range and turn the animation back on.
+                        if animate {
+                            ui.add(
+                                egui::Slider::new(&mut preferences.animation_duration, 0.05..=1.0)
+                                    .suffix(" s"),
+                            );
+                        }
+                    });
+                    ui.end_row();
+
+                    let mut sound = preferences.sound_volume > 0.0;
+
+                    ui.label("Sound");
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut sound, "Enabled").changed() {
+                            preferences.sound_volume = match sound {
+                                true => DEFAULT_SOUND_VOLUME,
+                                false => 0.0,
+                            };
+                        }
+
+                        if sound {
+                            ui.add(
+                                egui::Slider::new(&mut preferences.sound_volume, 0.05..=1.0)
+                                    .text("Volume"),
+                            );
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Moves");
+                    ui.vertical(|ui| {
+                        ui.checkbox(&mut preferences.auto_queen, "Auto-queen");
+                        ui.checkbox(&mut preferences.premoves_enabled, "Premoves");
+                    });
+                    ui.end_row();
+
+                    ui.label("Layout");
+                    ui.checkbox(&mut preferences.show_side_panel, "Show side panel");
+                    ui.end_row();
+                });
+
+            ui.separator();
+
+            if ui.button("Reset to defaults").clicked() {
+                *preferences = Preferences::default();
+                *piece_set_input = preferences.piece_set.clone();
+            }
+        });
+}
@@ -2,64 +2,75 @@ use std::sync::{Arc, Mutex};
 
 use chess_logic::Piece;
 use eframe::{
-    egui::{self, Widget},
-    emath::pos2,
+    egui::{self, Key, Widget},
+    emath::Pos2,
 };
 
-use super::piece_widget::PieceWidget;
+use super::{piece_textures::PieceTextures, piece_widget::PieceWidget};
 
+/// Pieces offered by the picker and the key that selects each of them.
+const CHOICES: [(Piece, Key); 4] = [
+    (Piece::Queen, Key::Q),
+    (Piece::Rook, Key::R),
+    (Piece::Bishop, Key::B),
+    (Piece::Knight, Key::N),
+];
+
+pub enum PromotionChoice {
+    /// The move that led to the promotion should be taken back.
+    Cancel,
+    Piece(Piece),
+}
+
+/// Column of the pieces a pawn can be promoted to, drawn in the color of the
+/// promoting player at `pos`.
+///
+/// Besides clicking, the pieces can be picked with Q, R, B and N. Escape
+/// cancels the promotion. The keys are ignored while a widget has the focus.
 pub fn promote_widget(
-    selected_piece: &mut Option<chess_logic::Piece>,
-    dynamic_texture_manager: Arc<Mutex<egui_extras::DynamicTextureManager>>,
+    choice: &mut Option<PromotionChoice>,
+    color: chess_logic::Color,
+    pos: Pos2,
+    piece_textures: Arc<Mutex<PieceTextures>>,
 ) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| {
+        // The keys belong to the text field being typed in, if there is one.
+        if ui.memory().focus().is_none() {
+            let input = ui.input();
+
+            for (piece, key) in &CHOICES {
+                if input.key_pressed(*key) {
+                    *choice = Some(PromotionChoice::Piece(*piece));
+                }
+            }
+
+            if input.key_pressed(Key::Escape) {
+                *choice = Some(PromotionChoice::Cancel);
+            }
+        }
+
         egui::Area::new("promote piece area")
-            .fixed_pos(pos2(0.0, 0.0))
+            .fixed_pos(pos)
+            .order(egui::Order::Foreground)
             .show(ui.ctx(), |ui| {
                 egui::Frame::window(&ui.style()).show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        if ui
-                            .add(PieceWidget::new(
-                                Piece::Bishop,
-                                chess_logic::Color::Black,
-                                dynamic_texture_manager.clone(),
-                            ))
-                            .clicked()
-                        {
-                            *selected_piece = Some(Piece::Bishop);
-                        }
-
-                        if ui
-                            .add(PieceWidget::new(
-                                Piece::Knight,
-                                chess_logic::Color::Black,
-                                dynamic_texture_manager.clone(),
-                            ))
-                            .clicked()
-                        {
-                            *selected_piece = Some(Piece::Knight);
-                        };
+                    ui.vertical_centered(|ui| {
+                        for (piece, key) in &CHOICES {
+                            let response = ui
+                                .add(PieceWidget::new(
+                                    *piece,
+                                    color.clone(),
+                                    piece_textures.clone(),
+                                ))
+                                .on_hover_text(format!("{:?} ({:?})", piece, key));
 
-                        if ui
-                            .add(PieceWidget::new(
-                                Piece::Queen,
-                                chess_logic::Color::Black,
-                                dynamic_texture_manager.clone(),
-                            ))
-                            .clicked()
-                        {
-                            *selected_piece = Some(Piece::Queen);
+                            if response.clicked() {
+                                *choice = Some(PromotionChoice::Piece(*piece));
+                            }
                         }
 
-                        if ui
-                            .add(PieceWidget::new(
-                                Piece::Rook,
-                                chess_logic::Color::Black,
-                                dynamic_texture_manager.clone(),
-                            ))
-                            .clicked()
-                        {
-                            *selected_piece = Some(Piece::Rook);
+                        if ui.button("✖").on_hover_text("Cancel (Esc)").clicked() {
+                            *choice = Some(PromotionChoice::Cancel);
                         }
                     });
                 })
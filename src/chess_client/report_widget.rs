@@ -0,0 +1,163 @@
+use chess_logic::Color;
+use eframe::{egui, epaint::Color32};
+
+use crate::{
+    history::NodeId,
+    pgn,
+    review::{GameReport, GameReview, MoveClass},
+};
+
+use super::board_widget::BoardWidget;
+
+/// Review of the game of a single tab, started by the user.
+pub struct GameReviewer {
+    report: Option<GameReport>,
+    /// Running review, replaced by the report once it is finished.
+    review: Option<GameReview>,
+}
+
+impl GameReviewer {
+    pub fn new() -> Self {
+        Self {
+            report: None,
+            review: None,
+        }
+    }
+
+    /// Continues the running review and annotates the game with the report
+    /// once it is finished. Has to be called every frame.
+    pub fn update(&mut self, ctx: &egui::Context, board: &mut BoardWidget) {
+        let history = &board.game().history;
+
+        if !matches!(&self.report, Some(report) if report.matches(history)) {
+            self.report = None;
+        }
+
+        let review = match &mut self.review {
+            Some(review) => review,
+            None => return,
+        };
+
+        review.poll();
+
+        match review.report() {
+            // The game was replaced while it was reviewed.
+            Some(report) if !report.matches(&board.game().history) => self.review = None,
+            Some(report) => {
+                board.apply_report(&report);
+                self.report = Some(report);
+                self.review = None;
+            }
+            None => ctx.request_repaint(),
+        }
+    }
+}
+
+/// Button to review the game, the progress of the review and the report:
+/// the accuracy of both players and their flagged moves, which can be
+/// clicked to show them on the board.
+pub fn report_widget<'a>(
+    reviewer: &'a mut GameReviewer,
+    board: &'a BoardWidget,
+    shown_move: &'a mut Option<NodeId>,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        ui.vertical(|ui| {
+            let history = &board.game().history;
+
+            match reviewer.review.as_ref().map(GameReview::progress) {
+                Some((done, total)) => {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Reviewing position {} of {}…", done + 1, total));
+
+                        if ui.button("Cancel").clicked() {
+                            reviewer.review = None;
+                        }
+                    });
+                }
+                None => {
+                    if ui
+                        .add_enabled(!history.is_empty(), egui::Button::new("🔍 Review game"))
+                        .on_hover_text("Let the computer rate every move and annotate the mistakes")
+                        .clicked()
+                    {
+                        reviewer.review = Some(GameReview::start(history));
+                    }
+                }
+            }
+
+            let report = match &reviewer.report {
+                Some(report) => report,
+                None => return,
+            };
+
+            egui::Grid::new("review summary").show(ui, |ui| {
+                for header in ["", "Accuracy", "?!", "?", "??"] {
+                    ui.label(header);
+                }
+
+                ui.end_row();
+
+                for (name, color) in [("White", Color::White), ("Black", Color::Black)] {
+                    ui.label(name);
+                    ui.label(match report.accuracy(&color) {
+                        Some(accuracy) => format!("{:.0}%", accuracy),
+                        None => "–".to_owned(),
+                    });
+
+                    for class in [
+                        MoveClass::Inaccuracy,
+                        MoveClass::Mistake,
+                        MoveClass::Blunder,
+                    ] {
+                        ui.label(report.count(&color, class).to_string());
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+            let flagged_moves = report
+                .moves
+                .iter()
+                .filter(|review| review.class.nag().is_some());
+
+            egui::CollapsingHeader::new("Flagged moves").show(ui, |ui| {
+                for review in flagged_moves {
+                    let number = pgn::get_move_number(history, review.id);
+                    let separator = match review.color {
+                        Color::White => ".",
+                        Color::Black => "...",
+                    };
+                    let symbol = review.class.nag().and_then(pgn::nag_symbol).unwrap_or("");
+                    let text = format!(
+                        "{}{} {}{} {}",
+                        number,
+                        separator,
+                        review.san,
+                        symbol,
+                        review.class.name()
+                    );
+                    let response = ui.selectable_label(
+                        history.current_node() == Some(review.id),
+                        egui::RichText::new(text).color(get_class_color(review.class)),
+                    );
+
+                    if response.clicked() {
+                        *shown_move = Some(review.id);
+                    }
+                }
+            });
+        })
+        .response
+    }
+}
+
+fn get_class_color(class: MoveClass) -> Color32 {
+    match class {
+        MoveClass::Best | MoveClass::Good => Color32::GRAY,
+        MoveClass::Inaccuracy => Color32::from_rgb(200, 160, 0),
+        MoveClass::Mistake => Color32::from_rgb(230, 120, 0),
+        MoveClass::Blunder => Color32::from_rgb(210, 30, 30),
+    }
+}
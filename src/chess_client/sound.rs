@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+/// Volume sounds are played with unless another one is chosen.
+pub const DEFAULT_SOUND_VOLUME: f32 = 0.5;
+
+/// Event of the game a sound is played for. The sounds are short tones, so
+/// no audio files have to be shipped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sound {
+    Capture,
+    Check,
+    GameEnd,
+    Move,
+}
+
+impl Sound {
+    /// Sound of a move given in SAN, e.g. a check for `"Qxf7+"`.
+    pub fn of_move(san: &str, is_game_over: bool) -> Self {
+        match san {
+            _ if is_game_over => Sound::GameEnd,
+            san if san.ends_with('+') => Sound::Check,
+            san if san.contains('x') => Sound::Capture,
+            _ => Sound::Move,
+        }
+    }
+
+    fn frequency(&self) -> f32 {
+        match self {
+            Sound::Capture => 330.0,
+            Sound::Check => 880.0,
+            Sound::GameEnd => 523.25,
+            Sound::Move => 440.0,
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            Sound::GameEnd => Duration::from_millis(400),
+            _ => Duration::from_millis(70),
+        }
+    }
+
+    /// Plays the sound without waiting for it to end. Does nothing for a
+    /// volume of `0.0` or if there is no audio output.
+    pub fn play(&self, volume: f32) {
+        if volume > 0.0 {
+            output::play_tone(self.frequency(), self.duration(), volume);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod output {
+    use std::time::Duration;
+
+    use rodio::{source::SineWave, OutputStream, OutputStreamHandle, Source};
+
+    thread_local! {
+        /// Opened on the first sound. The stream has to be kept alive, and
+        /// it can't be sent to other threads.
+        static OUTPUT: Option<(OutputStream, OutputStreamHandle)> =
+            OutputStream::try_default().ok();
+    }
+
+    pub fn play_tone(frequency: f32, duration: Duration, volume: f32) {
+        OUTPUT.with(|output| {
+            if let Some((_, handle)) = output {
+                let tone = SineWave::new(frequency)
+                    .take_duration(duration)
+                    .amplify(volume);
+
+                let _ = handle.play_raw(tone);
+            }
+        });
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod output {
+    use std::time::Duration;
+
+    use web_sys::AudioContext;
+
+    thread_local! {
+        static CONTEXT: Option<AudioContext> = AudioContext::new().ok();
+    }
+
+    pub fn play_tone(frequency: f32, duration: Duration, volume: f32) {
+        CONTEXT.with(|context| {
+            if let Some(context) = context {
+                play_on(context, frequency, duration, volume);
+            }
+        });
+    }
+
+    fn play_on(
+        context: &AudioContext,
+        frequency: f32,
+        duration: Duration,
+        volume: f32,
+    ) -> Option<()> {
+        // Browsers suspend audio created before the user interacted with the
+        // page.
+        let _ = context.resume();
+
+        let oscillator = context.create_oscillator().ok()?;
+        let gain = context.create_gain().ok()?;
+        let start = context.current_time();
+
+        oscillator.frequency().set_value(frequency);
+        gain.gain().set_value(volume);
+        oscillator.connect_with_audio_node(&gain).ok()?;
+        gain.connect_with_audio_node(&context.destination()).ok()?;
+        oscillator.start_with_when(start).ok()?;
+        oscillator
+            .stop_with_when(start + duration.as_secs_f64())
+            .ok()
+    }
+}
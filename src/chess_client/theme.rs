@@ -0,0 +1,118 @@
+use eframe::{
+    egui,
+    epaint::{Color32, Stroke},
+};
+
+/// Look of the whole client, including the board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VisualTheme {
+    Dark,
+    HighContrast,
+    Light,
+}
+
+impl VisualTheme {
+    pub const ALL: [Self; 3] = [Self::Light, Self::Dark, Self::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Dark => "Dark",
+            Self::HighContrast => "High contrast",
+            Self::Light => "Light",
+        }
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        match self {
+            Self::Dark => egui::Visuals::dark(),
+            Self::HighContrast => high_contrast_visuals(),
+            Self::Light => egui::Visuals::light(),
+        }
+    }
+
+    pub fn board_colors(&self) -> BoardColors {
+        match self {
+            Self::Dark => BoardColors {
+                check: Color32::from_rgba_premultiplied(150, 0, 0, 150),
+                cursor: Color32::from_rgb(86, 180, 233),
+                dark_square: Color32::from_rgb(105, 90, 75),
+                last_move: Color32::from_rgba_premultiplied(90, 90, 0, 90),
+                light_square: Color32::from_rgb(170, 162, 150),
+                marker_outline: Color32::from_black_alpha(160),
+                move_marker: Color32::from_rgb(86, 180, 233),
+                premove: Color32::from_rgba_premultiplied(60, 20, 90, 110),
+                take_marker: Color32::from_rgb(230, 159, 0),
+            },
+            Self::HighContrast => BoardColors {
+                check: Color32::from_rgba_premultiplied(200, 0, 0, 200),
+                cursor: Color32::from_rgb(255, 255, 0),
+                dark_square: Color32::from_rgb(90, 90, 90),
+                last_move: Color32::from_rgba_premultiplied(0, 110, 110, 140),
+                light_square: Color32::WHITE,
+                marker_outline: Color32::BLACK,
+                move_marker: Color32::from_rgb(255, 255, 0),
+                premove: Color32::from_rgba_premultiplied(90, 0, 140, 160),
+                take_marker: Color32::from_rgb(255, 120, 0),
+            },
+            Self::Light => BoardColors {
+                check: Color32::from_rgba_premultiplied(150, 0, 0, 150),
+                cursor: Color32::from_rgb(0, 120, 215),
+                dark_square: Color32::BROWN,
+                last_move: Color32::from_rgba_premultiplied(90, 90, 0, 90),
+                light_square: Color32::WHITE,
+                marker_outline: Color32::from_white_alpha(200),
+                move_marker: Color32::from_rgb(0, 114, 178),
+                premove: Color32::from_rgba_premultiplied(60, 20, 90, 110),
+                take_marker: Color32::from_rgb(230, 159, 0),
+            },
+        }
+    }
+}
+
+/// Colors used to paint the board.
+///
+/// The move and take markers use colors that stay apart for the common
+/// kinds of color blindness (blue and orange of the Okabe-Ito palette) and
+/// differ in shape as well: a dot for moves, a ring for takes.
+#[derive(Clone, Debug)]
+pub struct BoardColors {
+    pub check: Color32,
+    pub cursor: Color32,
+    pub dark_square: Color32,
+    pub last_move: Color32,
+    pub light_square: Color32,
+    /// Drawn around the markers, so they stand out on both square colors.
+    pub marker_outline: Color32,
+    pub move_marker: Color32,
+    pub premove: Color32,
+    pub take_marker: Color32,
+}
+
+fn high_contrast_visuals() -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::BLACK;
+    visuals.window_stroke = Stroke::new(2.0, Color32::WHITE);
+    visuals.hyperlink_color = Color32::from_rgb(255, 255, 0);
+    visuals.selection.bg_fill = Color32::from_rgb(0, 70, 160);
+    visuals.selection.stroke = Stroke::new(2.0, Color32::from_rgb(255, 255, 0));
+
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.bg_fill = Color32::BLACK;
+        widget.bg_stroke = Stroke::new(1.5, Color32::WHITE);
+        widget.fg_stroke = Stroke::new(1.5, Color32::WHITE);
+    }
+
+    visuals.widgets.hovered.bg_stroke = Stroke::new(2.0, Color32::from_rgb(255, 255, 0));
+    visuals.widgets.active.bg_stroke = Stroke::new(2.0, Color32::from_rgb(255, 255, 0));
+
+    visuals
+}
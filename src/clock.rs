@@ -0,0 +1,418 @@
+use chess_logic::Player;
+
+use crate::rules;
+
+/// How time is added after a move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bonus {
+    None,
+    /// The increment is always added after the move.
+    Fischer(f64),
+    /// The time used for the move is given back, up to the delay.
+    Bronstein(f64),
+    /// The clock only starts counting down after the delay passed.
+    SimpleDelay(f64),
+}
+
+/// Part of a time control, e.g. "40 moves in 90 minutes".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage {
+    pub bonus: Bonus,
+    /// Number of moves that have to be made in this stage, `None` for the
+    /// rest of the game.
+    pub moves: Option<u32>,
+    /// Time in seconds that is added when the stage starts.
+    pub time: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeControl {
+    pub stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: f64) -> Self {
+        Self::single_stage(time, Bonus::None)
+    }
+
+    pub fn fischer(time: f64, increment: f64) -> Self {
+        Self::single_stage(time, Bonus::Fischer(increment))
+    }
+
+    pub fn bronstein(time: f64, delay: f64) -> Self {
+        Self::single_stage(time, Bonus::Bronstein(delay))
+    }
+
+    pub fn simple_delay(time: f64, delay: f64) -> Self {
+        Self::single_stage(time, Bonus::SimpleDelay(delay))
+    }
+
+    fn single_stage(time: f64, bonus: Bonus) -> Self {
+        Self {
+            stages: vec![Stage {
+                bonus,
+                moves: None,
+                time,
+            }],
+        }
+    }
+
+    /// Parses the PGN `TimeControl` format with the common extensions for
+    /// delays, e.g. `"300+2"`, `"40/5400+30:1800+30"`, `"900b10"` (Bronstein)
+    /// or `"300d5"` (simple delay). Times have to be positive, bonuses can't
+    /// be negative and stages need at least one move.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut stages = Vec::new();
+
+        for ser_stage in text.split(':') {
+            let (moves, rest) = match ser_stage.split_once('/') {
+                Some((moves, rest)) => (Some(moves.parse().ok().filter(|moves| *moves > 0)?), rest),
+                None => (None, ser_stage),
+            };
+
+            let (time, bonus) = if let Some((time, bonus)) = rest.split_once('+') {
+                (time, Bonus::Fischer(parse_seconds(bonus, 0.0)?))
+            } else if let Some((time, bonus)) = rest.split_once('b') {
+                (time, Bonus::Bronstein(parse_seconds(bonus, 0.0)?))
+            } else if let Some((time, bonus)) = rest.split_once('d') {
+                (time, Bonus::SimpleDelay(parse_seconds(bonus, 0.0)?))
+            } else {
+                (rest, Bonus::None)
+            };
+
+            let time = parse_seconds(time, f64::MIN_POSITIVE)?;
+
+            stages.push(Stage { bonus, moves, time });
+        }
+
+        Some(Self { stages })
+    }
+
+    /// Inverse of [`Self::parse`].
+    pub fn format(&self) -> String {
+        let stages: Vec<String> = self
+            .stages
+            .iter()
+            .map(|stage| {
+                let moves = match stage.moves {
+                    Some(moves) => format!("{}/", moves),
+                    None => String::new(),
+                };
+                let bonus = match stage.bonus {
+                    Bonus::None => String::new(),
+                    Bonus::Fischer(increment) => format!("+{}", increment),
+                    Bonus::Bronstein(delay) => format!("b{}", delay),
+                    Bonus::SimpleDelay(delay) => format!("d{}", delay),
+                };
+
+                format!("{}{}{}", moves, stage.time, bonus)
+            })
+            .collect();
+
+        stages.join(":")
+    }
+}
+
+/// State of the clock of one player.
+#[derive(Clone, Debug)]
+struct Side {
+    moves_in_stage: u32,
+    remaining: f64,
+    stage: usize,
+}
+
+/// Chess clock for two players. All times are in seconds, `now` is any
+/// monotonic time, e.g. `egui::InputState::time`.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+    flagged: Option<Player>,
+    running: Option<(Player, f64)>,
+    sides: [Side; 2],
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let start_time = control.stages.first().map(|s| s.time).unwrap_or(0.0);
+        let side = Side {
+            moves_in_stage: 0,
+            remaining: start_time,
+            stage: 0,
+        };
+
+        Self {
+            control,
+            flagged: None,
+            running: None,
+            sides: [side.clone(), side],
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// Starts the clock of the player.
+    pub fn start(&mut self, player: &Player, now: f64) {
+        if self.flagged.is_none() {
+            self.running = Some((player.clone(), now));
+        }
+    }
+
+    pub fn stop(&mut self, now: f64) {
+        if let Some((player, _)) = &self.running {
+            let remaining = self.remaining(player, now);
+
+            self.sides[side_index(player)].remaining = remaining;
+        }
+
+        self.running = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn running_player(&self) -> Option<&Player> {
+        self.running.as_ref().map(|(player, _)| player)
+    }
+
+    /// Ends the turn of the running player after they made a move, applies
+    /// the bonus of their stage and starts the clock of the opponent.
+    pub fn press(&mut self, now: f64) {
+        let (player, started) = match self.running.take() {
+            Some(running) => running,
+            None => return,
+        };
+
+        if self.check_flag(&player, started, now) {
+            return;
+        }
+
+        let elapsed = now - started;
+        let control = &self.control;
+        let side = &mut self.sides[side_index(&player)];
+        let stage = &control.stages[side.stage];
+
+        side.remaining -= match stage.bonus {
+            Bonus::SimpleDelay(delay) => (elapsed - delay).max(0.0),
+            _ => elapsed,
+        };
+        side.remaining += match stage.bonus {
+            Bonus::None | Bonus::SimpleDelay(_) => 0.0,
+            Bonus::Fischer(increment) => increment,
+            Bonus::Bronstein(delay) => elapsed.min(delay),
+        };
+
+        side.moves_in_stage += 1;
+
+        // The last stage repeats if it has a number of moves, like `40/7200`.
+        if Some(side.moves_in_stage) == stage.moves {
+            side.stage = (side.stage + 1).min(control.stages.len() - 1);
+            side.moves_in_stage = 0;
+            side.remaining += control.stages[side.stage].time;
+        }
+
+        self.running = Some((rules::opponent_of(&player), now));
+    }
+
+    /// Time the player has left, including the running turn.
+    pub fn remaining(&self, player: &Player, now: f64) -> f64 {
+        let side = &self.sides[side_index(player)];
+
+        match &self.running {
+            Some((running, started)) if side_index(running) == side_index(player) => {
+                let elapsed = now - started;
+                let used = match self.control.stages[side.stage].bonus {
+                    Bonus::SimpleDelay(delay) => (elapsed - delay).max(0.0),
+                    _ => elapsed,
+                };
+
+                (side.remaining - used).max(0.0)
+            }
+            _ => side.remaining,
+        }
+    }
+
+    /// Checks whether the running player ran out of time and stops the clock
+    /// if so. Returns the player whose flag fell.
+    pub fn update(&mut self, now: f64) -> Option<&Player> {
+        if let Some((player, started)) = self.running.clone() {
+            self.check_flag(&player, started, now);
+        }
+
+        self.flagged.as_ref()
+    }
+
+    pub fn flagged(&self) -> Option<&Player> {
+        self.flagged.as_ref()
+    }
+
+    fn check_flag(&mut self, player: &Player, started: f64, now: f64) -> bool {
+        self.running = Some((player.clone(), started));
+
+        if self.remaining(player, now) > 0.0 {
+            return false;
+        }
+
+        self.sides[side_index(player)].remaining = 0.0;
+        self.running = None;
+        self.flagged = Some(player.clone());
+
+        true
+    }
+}
+
+/// Parses a number of seconds that is finite and at least `min`.
+fn parse_seconds(text: &str, min: f64) -> Option<f64> {
+    let seconds: f64 = text.parse().ok()?;
+
+    match seconds.is_finite() && seconds >= min {
+        true => Some(seconds),
+        false => None,
+    }
+}
+
+fn side_index(player: &Player) -> usize {
+    match player {
+        Player::You => 0,
+        Player::Opponent => 1,
+    }
+}
+
+/// Formats seconds as `h:mm:ss`, `m:ss`, or `s.t` below ten seconds.
+pub fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0);
+
+    if seconds < 10.0 {
+        return format!("{:.1}", seconds);
+    }
+
+    let total = seconds.floor() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lets the players alternately make a move at the given times, starting
+    /// with [`Player::You`] at `0.0`.
+    fn play(control: TimeControl, move_times: &[f64]) -> Clock {
+        let mut clock = Clock::new(control);
+
+        clock.start(&Player::You, 0.0);
+
+        for now in move_times {
+            clock.press(*now);
+        }
+
+        clock
+    }
+
+    #[test]
+    fn parse_and_format_round_trip() {
+        for text in [
+            "300",
+            "300+2",
+            "40/5400+30:1800+30",
+            "900b10",
+            "300d5",
+            "0.5+0",
+        ] {
+            assert_eq!(TimeControl::parse(text).unwrap().format(), text);
+        }
+
+        assert_eq!(
+            TimeControl::parse("40/5400+30:1800+30").unwrap().stages[0],
+            Stage {
+                bonus: Bonus::Fischer(30.0),
+                moves: Some(40),
+                time: 5400.0,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_times() {
+        for text in [
+            "", "abc", "-300", "0", "0/60", "NaN", "inf", "300+-2", "300+inf", "60:",
+        ] {
+            assert_eq!(TimeControl::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn fischer_adds_the_increment() {
+        let clock = play(TimeControl::fischer(60.0, 2.0), &[10.0]);
+
+        assert_eq!(clock.remaining(&Player::You, 10.0), 52.0);
+        assert_eq!(clock.running_player(), Some(&Player::Opponent));
+    }
+
+    #[test]
+    fn bronstein_gives_back_the_used_time_up_to_the_delay() {
+        let clock = play(TimeControl::bronstein(60.0, 5.0), &[3.0, 4.0, 12.0]);
+
+        assert_eq!(clock.remaining(&Player::Opponent, 12.0), 60.0);
+        assert_eq!(clock.remaining(&Player::You, 12.0), 57.0);
+    }
+
+    #[test]
+    fn simple_delay_starts_counting_after_the_delay() {
+        let mut clock = play(TimeControl::simple_delay(60.0, 5.0), &[3.0]);
+
+        assert_eq!(clock.remaining(&Player::You, 3.0), 60.0);
+        assert_eq!(clock.remaining(&Player::Opponent, 7.0), 60.0);
+        assert_eq!(clock.remaining(&Player::Opponent, 9.0), 59.0);
+
+        clock.press(11.0);
+        assert_eq!(clock.remaining(&Player::Opponent, 11.0), 57.0);
+    }
+
+    #[test]
+    fn next_stage_adds_its_time() {
+        let control = TimeControl::parse("2/60:30").unwrap();
+        let clock = play(control, &[10.0, 20.0, 30.0]);
+
+        assert_eq!(clock.remaining(&Player::You, 30.0), 70.0);
+        assert_eq!(clock.remaining(&Player::Opponent, 30.0), 50.0);
+    }
+
+    #[test]
+    fn last_stage_with_moves_repeats() {
+        let control = TimeControl::parse("2/60").unwrap();
+        let clock = play(control, &[10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0]);
+
+        assert_eq!(clock.remaining(&Player::You, 70.0), 140.0);
+        assert_eq!(clock.remaining(&Player::Opponent, 70.0), 90.0);
+    }
+
+    #[test]
+    fn flag_falls_when_the_time_is_up() {
+        let mut clock = play(TimeControl::sudden_death(10.0), &[]);
+
+        assert_eq!(clock.update(5.0), None);
+        assert_eq!(clock.update(11.0), Some(&Player::You));
+        assert_eq!(clock.remaining(&Player::You, 11.0), 0.0);
+        assert!(!clock.is_running());
+
+        // Moving after the flag fell does not bring the time back.
+        clock.press(12.0);
+        assert_eq!(clock.flagged(), Some(&Player::You));
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format_time(9.24), "9.2");
+        assert_eq!(format_time(75.9), "1:15");
+        assert_eq!(format_time(3725.0), "1:02:05");
+        assert_eq!(format_time(-1.0), "0.0");
+    }
+}
@@ -3,20 +3,81 @@ use std::error::Error;
 use chess_logic::{board::PieceInstance, Board, Color, Piece, Player};
 use yaml_rust::Yaml;
 
-pub fn deserialize_game(ser_game: &Yaml) -> Result<Board, Box<dyn Error>> {
-    deserialize_board(&ser_game["board"])
+use crate::{
+    annotations::{AnnotationStore, Annotations},
+    history::{History, NodeId},
+    notation,
+    outcome::{GameResult, Termination},
+    rules, Game,
+};
+
+pub fn deserialize_game(ser_game: &Yaml) -> Result<Game, Box<dyn Error>> {
+    // Games saved before the colors were stored always gave the user black.
+    let your_color = match &ser_game["your_color"] {
+        Yaml::BadValue => Color::Black,
+        ser_color => deserialize_color(ser_color)?,
+    };
+    let board = deserialize_board(&ser_game["board"], &your_color)?;
+    let annotations = deserialize_annotations(&ser_game["annotations"], &board)?;
+
+    let mut game = match &ser_game["history"] {
+        // Games saved before the history existed start at the saved board.
+        Yaml::BadValue => match &ser_game["to_move"] {
+            Yaml::BadValue => Game::new(board),
+            ser_player => Game::new_with_player(board, deserialize_player(ser_player)?),
+        },
+        ser_history => {
+            let history = deserialize_history(ser_history, &your_color)?;
+            let current = history.current();
+
+            // The replayed positions are more exact than the saved board,
+            // since they keep flags like `was_moved`.
+            Game {
+                annotations: AnnotationStore::default(),
+                board: history.board_at(current).clone(),
+                clock: None,
+                result: None,
+                to_move: history.player_to_move_at(current),
+                history,
+            }
+        }
+    };
+
+    game.annotations = annotations;
+    game.result = match &ser_game["result"] {
+        Yaml::BadValue | Yaml::Null => None,
+        ser_result => Some(deserialize_result(ser_result)?),
+    };
+
+    Ok(game)
 }
 
-fn deserialize_board(ser_board: &Yaml) -> Result<Board, Box<dyn Error>> {
-    let mut board = Board::new(Color::Black, Color::White);
+fn deserialize_board(ser_board: &Yaml, your_color: &Color) -> Result<Board, Box<dyn Error>> {
+    let opponent_color = match your_color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    };
+    let mut board = Board::new(your_color.clone(), opponent_color);
+    let ser_rows = ser_board.as_vec().ok_or("failed to deserialize board")?;
+
+    for (y, row) in ser_rows.iter().enumerate() {
+        let ser_row = row.as_vec().ok_or("failed to deserialize board row")?;
 
-    for (y, row) in ser_board.as_vec().unwrap().iter().enumerate() {
-        for (x, entry) in row.as_vec().unwrap().iter().enumerate() {
+        for (x, entry) in ser_row.iter().enumerate() {
             if !entry.is_null() {
                 let player = deserialize_player(&entry["player"])?;
                 let piece = deserialize_piece(&entry["piece"])?;
-                
-                let ins = PieceInstance::new(player, piece);
+
+                let mut ins = PieceInstance::new(player, piece);
+
+                // The flags hold the castling and en passant rights.
+                if let Some(was_moved) = entry["was_moved"].as_bool() {
+                    ins.was_moved = was_moved;
+                }
+
+                if let Some(is_eligible) = entry["is_eligible_for_en_passant"].as_bool() {
+                    ins.is_eligible_for_en_passant = is_eligible;
+                }
 
                 board.set(x, y, Some(ins));
             }
@@ -26,8 +87,16 @@ fn deserialize_board(ser_board: &Yaml) -> Result<Board, Box<dyn Error>> {
     Ok(board)
 }
 
+fn deserialize_color(ser_color: &Yaml) -> Result<Color, Box<dyn Error>> {
+    Ok(match ser_color.as_str().unwrap_or_default() {
+        "White" => Color::White,
+        "Black" => Color::Black,
+        val => return Err(format!("failed to deserialize color with value '{}'", val).into()),
+    })
+}
+
 fn deserialize_player(ser_player: &Yaml) -> Result<Player, Box<dyn Error>> {
-    Ok(match ser_player.as_str().unwrap() {
+    Ok(match ser_player.as_str().unwrap_or_default() {
         "You" => Player::You,
         "Opponent" => Player::Opponent,
         val => return Err(format!("failed to deserialize player with value '{}'", val).into()),
@@ -35,7 +104,7 @@ fn deserialize_player(ser_player: &Yaml) -> Result<Player, Box<dyn Error>> {
 }
 
 fn deserialize_piece(ser_ins: &Yaml) -> Result<Piece, Box<dyn Error>> {
-    Ok(match ser_ins.as_str().unwrap() {
+    Ok(match ser_ins.as_str().unwrap_or_default() {
         "Bishop" => Piece::Bishop,
         "King" => Piece::King,
         "Knight" => Piece::Knight,
@@ -45,3 +114,156 @@ fn deserialize_piece(ser_ins: &Yaml) -> Result<Piece, Box<dyn Error>> {
         val => return Err(format!("failed to deserialize piece with value '{}'", val).into()),
     })
 }
+
+fn deserialize_annotations(
+    ser_annotations: &Yaml,
+    board: &Board,
+) -> Result<AnnotationStore, Box<dyn Error>> {
+    let mut annotations = AnnotationStore::default();
+
+    // Games saved before annotations existed do not have the key.
+    if ser_annotations.is_badvalue() {
+        return Ok(annotations);
+    }
+
+    let ser_annotations = ser_annotations
+        .as_hash()
+        .ok_or("failed to deserialize annotations")?;
+
+    for (key, commands) in ser_annotations {
+        let key = key
+            .as_str()
+            .ok_or("failed to deserialize annotation key")?
+            .to_owned();
+        let commands = commands
+            .as_str()
+            .ok_or_else(|| format!("failed to deserialize annotations of '{}'", key))?;
+        let position_annotations = Annotations::from_pgn_commands(board, commands)?;
+
+        annotations.insert(key, position_annotations);
+    }
+
+    Ok(annotations)
+}
+
+fn deserialize_history(ser_history: &Yaml, your_color: &Color) -> Result<History, Box<dyn Error>> {
+    let start = deserialize_board(&ser_history["start"], your_color)?;
+    let start_player = deserialize_player(&ser_history["start_to_move"])?;
+
+    let mut history = History::new(start, start_player);
+
+    deserialize_line(&mut history, None, &ser_history["moves"])?;
+
+    match &ser_history["current"] {
+        // Games saved before variations existed store the index in the line.
+        Yaml::Integer(index) => history.go_to(*index as usize),
+        ser_current => {
+            let mut node = None;
+
+            let ser_path = ser_current
+                .as_vec()
+                .ok_or("failed to deserialize current move")?;
+
+            for ser_index in ser_path {
+                let index = ser_index
+                    .as_i64()
+                    .ok_or("failed to deserialize current move index")?
+                    as usize;
+                let child = history.children(node).get(index).copied();
+
+                node = Some(child.ok_or_else(|| {
+                    format!("failed to deserialize current move with index '{}'", index)
+                })?);
+            }
+
+            match node {
+                Some(id) => history.go_to_node(id),
+                None => history.go_to(0),
+            }
+        }
+    }
+
+    Ok(history)
+}
+
+/// Replays the moves of the line after the position of `parent`, together
+/// with their variations.
+fn deserialize_line(
+    history: &mut History,
+    mut parent: Option<NodeId>,
+    ser_line: &Yaml,
+) -> Result<(), Box<dyn Error>> {
+    let ser_entries = ser_line.as_vec().ok_or("failed to deserialize moves")?;
+
+    for ser_entry in ser_entries {
+        let ser_move = match ser_entry {
+            Yaml::Hash(_) => &ser_entry["move"],
+            _ => ser_entry,
+        };
+
+        let board = match parent {
+            Some(id) => history.ply(id).board.clone(),
+            None => history.start().clone(),
+        };
+        let uci = ser_move
+            .as_str()
+            .ok_or("failed to deserialize move that is not a string")?;
+        let mv = notation::parse_uci(&board, uci)
+            .ok_or_else(|| format!("failed to deserialize move with value '{}'", uci))?;
+        let after = rules::apply_move(&board, &mv)
+            .ok_or_else(|| format!("failed to replay move '{}'", uci))?;
+
+        match parent {
+            Some(id) => history.go_to_node(id),
+            None => history.go_to(0),
+        }
+
+        history.push(&board, &after, mv.from, mv.to);
+
+        let node = history.current_node();
+
+        if let Some(id) = node {
+            let nag = ser_entry["nag"].as_i64().map(|nag| nag as u8);
+            let comment = ser_entry["comment"].as_str().map(str::to_owned);
+
+            history.annotate_move(id, nag, comment);
+        }
+
+        if let Some(ser_variations) = ser_entry["variations"].as_vec() {
+            for ser_variation in ser_variations {
+                deserialize_line(history, parent, ser_variation)?;
+            }
+        }
+
+        parent = node;
+    }
+
+    Ok(())
+}
+
+fn deserialize_result(ser_result: &Yaml) -> Result<GameResult, Box<dyn Error>> {
+    let winner = match &ser_result["winner"] {
+        Yaml::Null => None,
+        ser_player => Some(deserialize_player(ser_player)?),
+    };
+
+    Ok(GameResult {
+        termination: deserialize_termination(&ser_result["termination"])?,
+        winner,
+    })
+}
+
+fn deserialize_termination(ser_termination: &Yaml) -> Result<Termination, Box<dyn Error>> {
+    Ok(match ser_termination.as_str().unwrap_or_default() {
+        "Checkmate" => Termination::Checkmate,
+        "FiftyMoveRule" => Termination::FiftyMoveRule,
+        "InsufficientMaterial" => Termination::InsufficientMaterial,
+        "Stalemate" => Termination::Stalemate,
+        "ThreefoldRepetition" => Termination::ThreefoldRepetition,
+        "Timeout" => Termination::Timeout,
+        "TimeoutVsInsufficientMaterial" => Termination::TimeoutVsInsufficientMaterial,
+        val => {
+            return Err(format!("failed to deserialize termination with value '{}'", val).into())
+        }
+    })
+}
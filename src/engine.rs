@@ -0,0 +1,813 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{sync::mpsc, time::Instant};
+
+use chess_logic::{Board, Color, Piece, Player};
+
+use crate::{
+    notation,
+    rules::{self, Move},
+};
+
+/// Score of being mated right now. Mates further away score less, so the
+/// search prefers the fastest mate.
+pub const MATE_SCORE: i32 = 100_000;
+/// Scores above this are mates.
+pub const MATE_THRESHOLD: i32 = MATE_SCORE - 1_000;
+const INFINITY: i32 = 1_000_000;
+
+/// Longest search on the web, where it runs on the thread of the page.
+#[cfg(target_arch = "wasm32")]
+const WEB_TIME_LIMIT: Duration = Duration::from_secs(3);
+/// Time the search runs per frame on the web, short enough for the page to
+/// stay responsive.
+#[cfg(target_arch = "wasm32")]
+const WEB_SLICE: Duration = Duration::from_millis(15);
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 8;
+
+/// Piece-square tables from white's point of view, `a8` first. Values are in
+/// centipawns and added to the value of the piece.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// Value of a piece in centipawns, as used by the search.
+pub fn piece_value(piece: &Piece) -> i32 {
+    match piece {
+        Piece::Bishop => 330,
+        Piece::King => 0,
+        Piece::Knight => 320,
+        Piece::Pawn => 100,
+        Piece::Queen => 900,
+        Piece::Rook => 500,
+    }
+}
+
+/// Static evaluation of the position in centipawns from the point of view of
+/// `player`: material plus a bonus for well placed pieces.
+pub fn evaluate(board: &Board, player: &Player) -> i32 {
+    let mut score = 0;
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let ins = match board.get(x, y) {
+                Some(ins) => ins,
+                None => continue,
+            };
+
+            let (file, rank) = notation::file_and_rank(board, (x, y));
+            let row = match board.get_color_of_player(&ins.player) {
+                Color::White => 8 - rank,
+                Color::Black => rank - 1,
+            };
+            let table = match ins.piece {
+                Piece::Bishop => &BISHOP_TABLE,
+                Piece::King => &KING_TABLE,
+                Piece::Knight => &KNIGHT_TABLE,
+                Piece::Pawn => &PAWN_TABLE,
+                Piece::Queen => &QUEEN_TABLE,
+                Piece::Rook => &ROOK_TABLE,
+            };
+            let value = piece_value(&ins.piece) + table[(row * 8 + file) as usize];
+
+            match &ins.player == player {
+                true => score += value,
+                false => score -= value,
+            }
+        }
+    }
+
+    score
+}
+
+/// When the search has to stop.
+#[derive(Clone, Debug)]
+pub struct SearchLimits {
+    pub depth: u32,
+    /// Number of best moves whose lines are reported, `1` when playing.
+    pub multi_pv: usize,
+    /// Up to this many centipawns are added to or taken from the score of
+    /// each move at the root, so weaker levels sometimes pick worse moves.
+    pub randomness: i32,
+    /// `None` searches until the depth is reached or the search is stopped.
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    /// Limits for the strength levels from [`MIN_LEVEL`] to [`MAX_LEVEL`].
+    pub fn for_level(level: u8) -> Self {
+        let level = level.clamp(MIN_LEVEL, MAX_LEVEL);
+        let (depth, randomness, seconds) = match level {
+            1 => (1, 300, 0.5),
+            2 => (1, 150, 0.5),
+            3 => (2, 80, 1.0),
+            4 => (2, 30, 1.0),
+            5 => (3, 15, 2.0),
+            6 => (3, 0, 3.0),
+            7 => (4, 0, 5.0),
+            _ => (5, 0, 10.0),
+        };
+
+        Self {
+            depth,
+            multi_pv: 1,
+            randomness,
+            time: Some(Duration::from_secs_f64(seconds)),
+        }
+    }
+}
+
+/// Outcome of a completed iteration of the search.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub nodes: u64,
+    /// Best line found, starting with the move to play.
+    pub pv: Vec<Move>,
+    /// In centipawns from the point of view of the player to move, see
+    /// [`MATE_SCORE`] for mates.
+    pub score: i32,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<&Move> {
+        self.pv.first()
+    }
+}
+
+/// Number of moves until mate for mate scores, negative if the player to
+/// move gets mated.
+pub fn mate_in(score: i32) -> Option<i32> {
+    match score {
+        score if score > MATE_THRESHOLD => Some((MATE_SCORE - score + 1) / 2),
+        score if score < -MATE_THRESHOLD => Some(-(MATE_SCORE + score + 1) / 2),
+        _ => None,
+    }
+}
+
+/// Formats a score like engines do, e.g. `"+0.35"` or `"#-3"`.
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:+.2}", score as f64 / 100.0),
+    }
+}
+
+/// Searches the best move of `player` with alpha-beta and iterative
+/// deepening. `on_info` is called after every completed depth with the best
+/// [`SearchLimits::multi_pv`] lines, the best one first.
+///
+/// Returns the result of the deepest completed iteration, or `None` if the
+/// player has no legal move.
+pub fn search(
+    board: &Board,
+    player: &Player,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    mut on_info: impl FnMut(&[SearchInfo]),
+) -> Option<SearchInfo> {
+    let mut sliced = SlicedSearch::new(board.clone(), player.clone(), limits.clone());
+
+    sliced.step(stop, None, &mut on_info);
+    sliced.result()
+}
+
+/// State of [`search`] between the root moves, so the search can be run in
+/// slices where there are no threads to run it on.
+pub struct SlicedSearch {
+    board: Board,
+    /// Result of the deepest completed iteration.
+    best: Option<SearchInfo>,
+    deadline: Option<Instant>,
+    depth: u32,
+    finished: bool,
+    limits: SearchLimits,
+    /// Root moves, the best one of the last iteration first.
+    moves: Vec<Move>,
+    /// Index of the root move that is searched next.
+    next_move: usize,
+    nodes: u64,
+    player: Player,
+    random: RandomState,
+    /// Noisy score, score and line of every root move of the running
+    /// iteration with an exact score, the best one first.
+    results: Vec<(i32, i32, Vec<Move>)>,
+    started: Instant,
+}
+
+impl SlicedSearch {
+    pub fn new(board: Board, player: Player, limits: SearchLimits) -> Self {
+        let started = Instant::now();
+        let mut moves = rules::legal_moves(&board, &player);
+
+        order_moves(&board, &mut moves);
+
+        Self {
+            board,
+            best: None,
+            deadline: limits.time.map(|time| started + time),
+            depth: 1,
+            finished: false,
+            limits,
+            moves,
+            next_move: 0,
+            nodes: 0,
+            player,
+            random: RandomState::new(),
+            results: Vec::new(),
+            started,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Searches root moves until the search is finished or `slice_end` has
+    /// passed, but at least one. A started root move is always searched to
+    /// the end, so a slice can take longer at high depths.
+    pub fn step(
+        &mut self,
+        stop: &AtomicBool,
+        slice_end: Option<Instant>,
+        on_info: &mut impl FnMut(&[SearchInfo]),
+    ) {
+        let mut searcher = Searcher {
+            aborted: false,
+            deadline: self.deadline,
+            nodes: self.nodes,
+            stop,
+        };
+
+        while !self.finished {
+            match self.moves.get(self.next_move) {
+                Some(mv) => {
+                    let mv = *mv;
+
+                    self.search_root_move(&mut searcher, mv);
+                    self.next_move += 1;
+                    self.nodes = searcher.nodes;
+                }
+                None => self.finish_iteration(on_info),
+            }
+
+            if searcher.aborted {
+                self.finished = true;
+            }
+
+            if slice_end.map_or(false, |end| Instant::now() >= end) {
+                break;
+            }
+        }
+    }
+
+    fn search_root_move(&mut self, searcher: &mut Searcher, mv: Move) {
+        let after = match rules::apply_move(&self.board, &mv) {
+            Some(after) => after,
+            None => return,
+        };
+
+        let multi_pv = self.limits.multi_pv.max(1);
+        let mut child_pv = Vec::new();
+        // With randomness every move needs an exact score, otherwise a
+        // bound is enough for moves that can't be among the best lines.
+        let window_alpha = match self.results.len() {
+            _ if self.limits.randomness > 0 => -INFINITY,
+            found if found < multi_pv => -INFINITY,
+            _ => self.results[multi_pv - 1].1,
+        };
+        let score = -searcher.negamax(
+            &after,
+            &rules::opponent_of(&self.player),
+            self.depth - 1,
+            -INFINITY,
+            -window_alpha,
+            1,
+            &mut child_pv,
+        );
+
+        if searcher.aborted || score <= window_alpha {
+            return;
+        }
+
+        let noise = get_noise(&self.random, self.next_move, self.limits.randomness);
+        let mut pv = vec![mv];
+
+        pv.extend(child_pv);
+        self.results.push((score + noise, score, pv));
+        // Stable, so the earlier move stays first among equal scores.
+        self.results.sort_by_key(|(noisy_score, _, _)| -noisy_score);
+    }
+
+    fn finish_iteration(&mut self, on_info: &mut impl FnMut(&[SearchInfo])) {
+        if self.results.is_empty() {
+            self.finished = true;
+            return;
+        }
+
+        let lines: Vec<SearchInfo> = std::mem::take(&mut self.results)
+            .into_iter()
+            .take(self.limits.multi_pv.max(1))
+            .map(|(_, score, pv)| SearchInfo {
+                depth: self.depth,
+                nodes: self.nodes,
+                pv,
+                score,
+            })
+            .collect();
+
+        on_info(&lines);
+
+        let info = lines.into_iter().next().unwrap();
+        let is_mate = info.score.abs() > MATE_THRESHOLD;
+
+        // Search the best move first in the next iteration.
+        if let Some(index) = self
+            .moves
+            .iter()
+            .position(|mv| Some(mv) == info.best_move())
+        {
+            let mv = self.moves.remove(index);
+
+            self.moves.insert(0, mv);
+        }
+
+        self.best = Some(info);
+
+        // The next iteration takes much longer than this one, so it would
+        // most likely not finish anyway.
+        let is_half_time_used = self
+            .limits
+            .time
+            .map_or(false, |time| self.started.elapsed() * 2 > time);
+
+        if is_mate || is_half_time_used || self.depth >= self.limits.depth {
+            self.finished = true;
+        } else {
+            self.depth += 1;
+            self.next_move = 0;
+        }
+    }
+
+    /// Result of the deepest completed iteration. Even if not a single one
+    /// finished, some legal move is better than none.
+    pub fn result(&self) -> Option<SearchInfo> {
+        self.best.clone().or_else(|| {
+            self.moves.first().map(|mv| SearchInfo {
+                depth: 0,
+                nodes: self.nodes,
+                pv: vec![*mv],
+                score: 0,
+            })
+        })
+    }
+}
+
+struct Searcher<'a> {
+    aborted: bool,
+    deadline: Option<Instant>,
+    nodes: u64,
+    stop: &'a AtomicBool,
+}
+
+impl Searcher<'_> {
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        self.nodes += 1;
+
+        if self.stop.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .map_or(false, |deadline| Instant::now() > deadline)
+        {
+            self.aborted = true;
+            return 0;
+        }
+
+        let mut moves = rules::legal_moves(board, player);
+
+        if moves.is_empty() {
+            return match rules::is_in_check(board, player) {
+                true => -MATE_SCORE + ply,
+                false => 0,
+            };
+        }
+
+        if depth == 0 {
+            return evaluate(board, player);
+        }
+
+        order_moves(board, &mut moves);
+
+        for mv in &moves {
+            let after = match rules::apply_move(board, mv) {
+                Some(after) => after,
+                None => continue,
+            };
+
+            let mut child_pv = Vec::new();
+            let score = -self.negamax(
+                &after,
+                &rules::opponent_of(player),
+                depth - 1,
+                -beta,
+                -alpha,
+                ply + 1,
+                &mut child_pv,
+            );
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(*mv);
+                pv.extend(child_pv);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        alpha
+    }
+}
+
+/// Puts captures of valuable pieces with cheap pieces and promotions first,
+/// so alpha-beta can cut off more.
+fn order_moves(board: &Board, moves: &mut [Move]) {
+    moves.sort_by_key(|mv| {
+        let victim = board
+            .get(mv.to.0, mv.to.1)
+            .map_or(0, |ins| piece_value(&ins.piece));
+        let attacker = board
+            .get(mv.from.0, mv.from.1)
+            .map_or(0, |ins| piece_value(&ins.piece));
+        let promotion = mv.promotion.as_ref().map_or(0, piece_value);
+
+        let mut key = promotion;
+
+        if victim > 0 {
+            key += 10 * victim - attacker;
+        }
+
+        -key
+    });
+}
+
+fn get_noise(random: &RandomState, index: usize, randomness: i32) -> i32 {
+    if randomness <= 0 {
+        return 0;
+    }
+
+    let mut hasher = random.build_hasher();
+
+    hasher.write_usize(index);
+
+    (hasher.finish() % (2 * randomness as u64 + 1)) as i32 - randomness
+}
+
+enum Message {
+    Info(Vec<SearchInfo>),
+    Finished(Option<SearchInfo>),
+}
+
+/// Search running on its own thread, so the UI stays responsive. On the web,
+/// where threads are not available, the search runs in short slices every
+/// time it is polled.
+pub struct BackgroundSearch {
+    finished: bool,
+    /// Lines of the deepest iteration completed so far, the best one first.
+    lines: Vec<SearchInfo>,
+    #[cfg(not(target_arch = "wasm32"))]
+    receiver: mpsc::Receiver<Message>,
+    #[cfg(target_arch = "wasm32")]
+    sliced: SlicedSearch,
+    stop: Arc<AtomicBool>,
+}
+
+impl BackgroundSearch {
+    pub fn start(board: Board, player: Player, limits: SearchLimits) -> Self {
+        // A root move is only interrupted by the time limit, so it bounds
+        // how long a slice can take.
+        #[cfg(target_arch = "wasm32")]
+        let limits = SearchLimits {
+            time: Some(
+                limits
+                    .time
+                    .map_or(WEB_TIME_LIMIT, |time| time.min(WEB_TIME_LIMIT)),
+            ),
+            ..limits
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let receiver = {
+            let (sender, receiver) = mpsc::channel();
+            let thread_stop = stop.clone();
+
+            std::thread::spawn(move || {
+                let info_sender = sender.clone();
+                let result = search(&board, &player, &limits, &thread_stop, |lines| {
+                    let _ = info_sender.send(Message::Info(lines.to_vec()));
+                });
+
+                let _ = sender.send(Message::Finished(result));
+            });
+
+            receiver
+        };
+
+        Self {
+            finished: false,
+            lines: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            receiver,
+            #[cfg(target_arch = "wasm32")]
+            sliced: SlicedSearch::new(board, player, limits),
+            stop,
+        }
+    }
+
+    /// Takes the messages of the search and returns whether new lines
+    /// arrived. Has to be called regularly, e.g. every frame.
+    pub fn poll(&mut self) -> bool {
+        let mut has_new_lines = false;
+
+        for message in self.take_messages() {
+            match message {
+                Message::Info(lines) => {
+                    self.lines = lines;
+                    has_new_lines = true;
+                }
+                Message::Finished(result) => {
+                    self.finished = true;
+
+                    // No iteration finished, but there is a move to play.
+                    if let (true, Some(result)) = (self.lines.is_empty(), result) {
+                        self.lines.push(result);
+                        has_new_lines = true;
+                    }
+                }
+            }
+        }
+
+        has_new_lines
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn take_messages(&mut self) -> Vec<Message> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Runs the next slice of the search.
+    #[cfg(target_arch = "wasm32")]
+    fn take_messages(&mut self) -> Vec<Message> {
+        let mut messages = Vec::new();
+
+        if self.finished {
+            return messages;
+        }
+
+        let slice_end = Instant::now() + WEB_SLICE;
+
+        self.sliced.step(&self.stop, Some(slice_end), &mut |lines| {
+            messages.push(Message::Info(lines.to_vec()));
+        });
+
+        if self.sliced.is_finished() {
+            messages.push(Message::Finished(self.sliced.result()));
+        }
+
+        messages
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Result of the deepest iteration completed so far.
+    pub fn latest(&self) -> Option<&SearchInfo> {
+        self.lines.first()
+    }
+
+    /// Best lines of the deepest iteration completed so far, see
+    /// [`SearchLimits::multi_pv`].
+    pub fn lines(&self) -> &[SearchInfo] {
+        &self.lines
+    }
+
+    /// Asks the search to finish. The deepest completed iteration is kept.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BackgroundSearch {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth,
+            multi_pv: 1,
+            randomness: 0,
+            time: None,
+        }
+    }
+
+    /// Best move in UCI notation and its score.
+    fn best_move(fen: &str, depth: u32) -> (String, i32) {
+        let (board, player) = notation::parse_fen(fen).unwrap();
+        let info = search(
+            &board,
+            &player,
+            &limits(depth),
+            &AtomicBool::new(false),
+            |_| (),
+        )
+        .unwrap();
+
+        (notation::format_uci(&board, &info.pv[0]), info.score)
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let (mv, score) = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+
+        assert_eq!(mv, "a1a8");
+        assert_eq!(mate_in(score), Some(1));
+    }
+
+    #[test]
+    fn takes_hanging_queen() {
+        let (mv, score) = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+
+        assert_eq!(mv, "d2d5");
+        assert!(score > 300);
+    }
+
+    #[test]
+    fn no_legal_move_has_no_result() {
+        let (board, player) = notation::parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert!(search(&board, &player, &limits(2), &AtomicBool::new(false), |_| ()).is_none());
+    }
+
+    #[test]
+    fn sliced_search_matches_search() {
+        let (board, player) =
+            notation::parse_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let stop = AtomicBool::new(false);
+        let whole = search(&board, &player, &limits(3), &stop, |_| ()).unwrap();
+        let mut sliced = SlicedSearch::new(board, player, limits(3));
+        let mut depths = Vec::new();
+
+        // Slices that already ended still search one root move each.
+        while !sliced.is_finished() {
+            sliced.step(&stop, Some(Instant::now()), &mut |lines| {
+                depths.push(lines[0].depth)
+            });
+        }
+
+        let result = sliced.result().unwrap();
+
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.pv, whole.pv);
+        assert_eq!(result.score, whole.score);
+    }
+
+    #[test]
+    fn mate_scores() {
+        assert_eq!(mate_in(MATE_SCORE - 1), Some(1));
+        assert_eq!(mate_in(MATE_SCORE - 5), Some(3));
+        assert_eq!(mate_in(-(MATE_SCORE - 4)), Some(-2));
+        assert_eq!(mate_in(MATE_THRESHOLD), None);
+        assert_eq!(mate_in(-250), None);
+    }
+
+    #[test]
+    fn formats_scores() {
+        assert_eq!(format_score(35), "+0.35");
+        assert_eq!(format_score(0), "+0.00");
+        assert_eq!(format_score(-120), "-1.20");
+        assert_eq!(format_score(MATE_SCORE - 5), "#3");
+        assert_eq!(format_score(-(MATE_SCORE - 4)), "#-2");
+    }
+
+    #[test]
+    fn levels_are_clamped() {
+        let same = |a: SearchLimits, b: SearchLimits| {
+            a.depth == b.depth && a.randomness == b.randomness && a.time == b.time
+        };
+
+        assert!(same(
+            SearchLimits::for_level(0),
+            SearchLimits::for_level(MIN_LEVEL)
+        ));
+        assert!(same(
+            SearchLimits::for_level(200),
+            SearchLimits::for_level(MAX_LEVEL)
+        ));
+        assert!(
+            SearchLimits::for_level(MAX_LEVEL).depth > SearchLimits::for_level(MIN_LEVEL).depth
+        );
+        assert_eq!(SearchLimits::for_level(MIN_LEVEL).multi_pv, 1);
+    }
+}
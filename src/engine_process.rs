@@ -0,0 +1,122 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, RecvTimeoutError, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long the engine gets to quit on its own before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// External engine running as a child process, talking line by line over its
+/// standard input and output. Both UCI and CECP engines quit on `quit`, which
+/// is sent when the process is dropped.
+pub(crate) struct EngineProcess {
+    /// Only `None` once dropped, when it is handed to the thread that waits
+    /// for the engine to quit.
+    child: Option<Child>,
+    exited: bool,
+    /// Lines read by a separate thread, so reading never blocks the UI.
+    receiver: mpsc::Receiver<String>,
+    stdin: ChildStdin,
+}
+
+impl EngineProcess {
+    pub fn start(program: &str) -> Result<Self, Box<dyn Error>> {
+        let mut child = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("failed to start engine '{}': {}", program, err))?;
+
+        let stdin = child.stdin.take().ok_or("failed to open engine input")?;
+        let stdout = child.stdout.take().ok_or("failed to open engine output")?;
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let sent = line.map(|line| sender.send(line));
+
+                if !matches!(sent, Ok(Ok(()))) {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child: Some(child),
+            exited: false,
+            receiver,
+            stdin,
+        })
+    }
+
+    /// Whether the engine closed its output, usually because it crashed.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn send(&mut self, command: &str) -> Result<(), Box<dyn Error>> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+
+        Ok(())
+    }
+
+    /// Waits for the next line until the deadline.
+    pub fn read_line(&mut self, deadline: Instant) -> Result<String, Box<dyn Error>> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        match self.receiver.recv_timeout(timeout) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err("engine did not answer in time".into()),
+            Err(RecvTimeoutError::Disconnected) => {
+                self.exited = true;
+                Err("engine exited".into())
+            }
+        }
+    }
+
+    /// Returns the next line if one was already read.
+    pub fn try_read_line(&mut self) -> Option<String> {
+        match self.receiver.try_recv() {
+            Ok(line) => Some(line),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.exited = true;
+                None
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        let mut child = match self.child.take() {
+            Some(child) => child,
+            None => return,
+        };
+
+        // The process is usually dropped by the UI, which must not wait for
+        // the engine.
+        thread::spawn(move || {
+            let deadline = Instant::now() + QUIT_TIMEOUT;
+
+            while Instant::now() < deadline {
+                if !matches!(child.try_wait(), Ok(None)) {
+                    return;
+                }
+
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+    }
+}
@@ -0,0 +1,133 @@
+use chess_logic::{Board, Player};
+
+use crate::{
+    annotations::AnnotationStore,
+    clock::Clock,
+    history::{History, NodeId},
+    outcome::{self, GameResult},
+    rules::{self, Pos},
+};
+
+/// Everything that belongs to a single game and is saved with it.
+#[derive(Clone)]
+pub struct Game {
+    pub annotations: AnnotationStore,
+    /// The position currently shown, which is the one at
+    /// [`History::current`].
+    pub board: Board,
+    /// `None` for games without time control.
+    pub clock: Option<Clock>,
+    pub history: History,
+    /// Set as soon as the game ended, in the current position.
+    pub result: Option<GameResult>,
+    pub to_move: Player,
+}
+
+impl Game {
+    pub fn new(board: Board) -> Self {
+        let to_move = rules::get_starting_player(&board);
+
+        Self::new_with_player(board, to_move)
+    }
+
+    /// Starts a game from the board with `to_move` making the first move.
+    pub fn new_with_player(board: Board, to_move: Player) -> Self {
+        Self {
+            annotations: AnnotationStore::default(),
+            clock: None,
+            history: History::new(board.clone(), to_move.clone()),
+            result: None,
+            board,
+            to_move,
+        }
+    }
+
+    /// Adds the move that changed `before` into the current board to the
+    /// history.
+    pub fn record_move(&mut self, before: &Board, from: Pos, to: Pos) {
+        self.history.push(before, &self.board, from, to);
+        self.to_move = self.history.player_to_move_at(self.history.current());
+        self.update_result();
+    }
+
+    /// Checks whether the current position ended the game.
+    pub fn update_result(&mut self) {
+        self.result = outcome::detect_result(&self.history, &self.to_move);
+    }
+
+    /// Ends the game if the flag of the player to move fell. Cheap enough to
+    /// be called every frame.
+    pub fn update_clock(&mut self, now: f64) {
+        let flagged = match &mut self.clock {
+            Some(clock) if self.result.is_none() => clock.update(now).cloned(),
+            _ => None,
+        };
+
+        if let Some(player) = flagged {
+            self.result = Some(outcome::timeout_result(&self.board, &player));
+        }
+    }
+
+    /// Whether a clock is counting down. The history can't be browsed then,
+    /// since the clock does not know the times of earlier positions.
+    pub fn is_clock_running(&self) -> bool {
+        matches!(&self.clock, Some(clock) if clock.is_running())
+    }
+
+    /// Shows the position after the given ply (`0` being the start).
+    pub fn go_to_ply(&mut self, index: usize) {
+        self.history.go_to(index);
+
+        let current = self.history.current();
+
+        self.board = self.history.board_at(current).clone();
+        self.to_move = self.history.player_to_move_at(current);
+        self.update_result();
+    }
+
+    /// Shows the position after the node of the history tree.
+    pub fn go_to_node(&mut self, id: NodeId) {
+        self.history.go_to_node(id);
+        self.go_to_ply(self.history.current());
+    }
+
+    /// Removes the move with everything played after it.
+    pub fn delete_from(&mut self, id: NodeId) {
+        self.history.delete_from(id);
+        self.go_to_ply(self.history.current());
+    }
+
+    /// Whether the shown position is the last one of the line, so a move
+    /// continues the game instead of starting a variation.
+    pub fn is_at_line_end(&self) -> bool {
+        !self.history.can_redo()
+    }
+
+    /// No more moves can be made once the game is over.
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let undone = self.history.undo();
+
+        self.go_to_ply(self.history.current());
+
+        undone
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let redone = self.history.redo();
+
+        self.go_to_ply(self.history.current());
+
+        redone
+    }
+
+    /// Origin and destination of the move that led to the current position.
+    pub fn last_move(&self) -> Option<(Pos, Pos)> {
+        self.history
+            .move_to(self.history.current())
+            .map(|mv| (mv.from, mv.to))
+    }
+}
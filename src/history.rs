@@ -0,0 +1,520 @@
+use chess_logic::{Board, Piece, Player};
+
+use crate::{
+    notation,
+    rules::{self, Move, Pos},
+};
+
+/// Index of a node in the tree of a [`History`].
+pub type NodeId = usize;
+
+/// A single half move together with the position it led to.
+#[derive(Clone)]
+pub struct Ply {
+    pub board: Board,
+    /// Text shown after the move, exported as a PGN comment.
+    pub comment: Option<String>,
+    pub mv: Move,
+    /// Numeric Annotation Glyph like `2` for `?`, see [`crate::pgn::nag_symbol`].
+    pub nag: Option<u8>,
+    pub player: Player,
+    pub san: String,
+}
+
+/// Ply in the tree of moves, with the moves that were tried after it.
+#[derive(Clone)]
+struct Node {
+    /// The first child continues the line, the others are side variations.
+    children: Vec<NodeId>,
+    parent: Option<NodeId>,
+    ply: Ply,
+}
+
+/// Tree of moves played from a starting position.
+///
+/// Making a move other than the one that followed the current position adds
+/// a side variation instead of replacing the existing moves. Most methods
+/// work on the *line*: the moves leading to the current position, followed
+/// by the first continuation of each position after it. Indices are
+/// positions in that line, `0` being the starting position.
+///
+/// Positions are stored as snapshots, so going back restores the board
+/// exactly, including flags like `was_moved`.
+#[derive(Clone)]
+pub struct History {
+    current: usize,
+    line: Vec<NodeId>,
+    /// Removed nodes stay in here, but are not reachable anymore.
+    nodes: Vec<Node>,
+    /// Moves from the starting position, the first one is the main line.
+    roots: Vec<NodeId>,
+    start: Board,
+    start_player: Player,
+}
+
+impl History {
+    pub fn new(start: Board, start_player: Player) -> Self {
+        Self {
+            current: 0,
+            line: Vec::new(),
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            start,
+            start_player,
+        }
+    }
+
+    /// Index of the shown position in the line.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Node of the shown position, `None` for the starting position.
+    pub fn current_node(&self) -> Option<NodeId> {
+        match self.current {
+            0 => None,
+            index => Some(self.line[index - 1]),
+        }
+    }
+
+    /// Number of plies in the line.
+    pub fn len(&self) -> usize {
+        self.line.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line.is_empty()
+    }
+
+    /// Plies of the line.
+    pub fn plies(&self) -> Vec<&Ply> {
+        self.line.iter().map(|id| &self.nodes[*id].ply).collect()
+    }
+
+    pub fn line(&self) -> &[NodeId] {
+        &self.line
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn start_player(&self) -> &Player {
+        &self.start_player
+    }
+
+    pub fn board_at(&self, index: usize) -> &Board {
+        match index {
+            0 => &self.start,
+            _ => &self.ply(self.line[index - 1]).board,
+        }
+    }
+
+    pub fn player_to_move_at(&self, index: usize) -> Player {
+        match index {
+            0 => self.start_player.clone(),
+            _ => rules::opponent_of(&self.ply(self.line[index - 1]).player),
+        }
+    }
+
+    /// Returns the move that led to the position, if it is not the start.
+    pub fn move_to(&self, index: usize) -> Option<&Move> {
+        match index {
+            0 => None,
+            _ => Some(&self.ply(self.line[index - 1]).mv),
+        }
+    }
+
+    pub fn go_to(&mut self, index: usize) {
+        self.current = index.min(self.line.len());
+    }
+
+    pub fn ply(&self, id: NodeId) -> &Ply {
+        &self.nodes[id].ply
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    /// Moves played from the position after the node, or from the starting
+    /// position for `None`.
+    pub fn children(&self, id: Option<NodeId>) -> &[NodeId] {
+        match id {
+            Some(id) => &self.nodes[id].children,
+            None => &self.roots,
+        }
+    }
+
+    /// Sets the glyph and the comment of the move, replacing the old ones.
+    pub fn annotate_move(&mut self, id: NodeId, nag: Option<u8>, comment: Option<String>) {
+        let ply = &mut self.nodes[id].ply;
+
+        ply.nag = nag;
+        ply.comment = comment;
+    }
+
+    /// Board before the move of the node was made.
+    pub fn board_before(&self, id: NodeId) -> &Board {
+        match self.nodes[id].parent {
+            Some(parent) => &self.ply(parent).board,
+            None => &self.start,
+        }
+    }
+
+    /// Whether the node is still part of the tree, which is not the case
+    /// once it was deleted.
+    pub fn contains(&self, id: NodeId) -> bool {
+        let node = match self.nodes.get(id) {
+            Some(node) => node,
+            None => return false,
+        };
+
+        self.children(node.parent).contains(&id)
+            && node.parent.map_or(true, |parent| self.contains(parent))
+    }
+
+    /// Whether the node is the first move of a side variation.
+    pub fn is_variation_start(&self, id: NodeId) -> bool {
+        self.children(self.nodes[id].parent).first() != Some(&id)
+    }
+
+    /// Number of plies from the starting position to the node.
+    pub fn depth(&self, id: NodeId) -> usize {
+        let mut depth = 1;
+        let mut node = id;
+
+        while let Some(parent) = self.nodes[node].parent {
+            depth += 1;
+            node = parent;
+        }
+
+        depth
+    }
+
+    /// Shows the position after the node. The line is changed to the one
+    /// leading through the node.
+    pub fn go_to_node(&mut self, id: NodeId) {
+        let mut path = vec![id];
+
+        while let Some(parent) = self.nodes[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+
+        path.reverse();
+
+        self.current = path.len();
+        self.line = path;
+        self.extend_line();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current < self.line.len()
+    }
+
+    /// Steps back one move. The move stays in the tree, so it can be
+    /// restored with [`Self::redo`], even after other moves were tried.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+
+        self.current -= 1;
+
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if !self.can_redo() {
+            return false;
+        }
+
+        self.current += 1;
+
+        true
+    }
+
+    /// Records a move made from the current position. If the move was
+    /// already played from here, its node is reused, otherwise it becomes
+    /// a new variation (or the continuation, if there was none).
+    ///
+    /// The promotion piece of the move is derived from the boards, so the
+    /// caller only needs to know the squares.
+    pub fn push(&mut self, before: &Board, after: &Board, from: Pos, to: Pos) {
+        let parent = self.current_node();
+        let mv = Move {
+            from,
+            promotion: get_promotion(before, after, from, to),
+            to,
+        };
+
+        let existing = self
+            .children(parent)
+            .iter()
+            .copied()
+            .find(|id| self.ply(*id).mv == mv);
+
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let player = self.player_to_move_at(self.current);
+                let id = self.nodes.len();
+
+                self.nodes.push(Node {
+                    children: Vec::new(),
+                    parent,
+                    ply: Ply {
+                        board: after.clone(),
+                        comment: None,
+                        san: notation::format_san(before, &player, &mv),
+                        mv,
+                        nag: None,
+                        player,
+                    },
+                });
+                self.children_mut(parent).push(id);
+
+                id
+            }
+        };
+
+        self.go_to_node(id);
+    }
+
+    /// Moves the variation the node belongs to one place up among the
+    /// alternatives at the position it branches off. Promoting the first
+    /// side variation makes it the main line.
+    pub fn promote_variation(&mut self, id: NodeId) -> bool {
+        let node = self.find_ancestor(id, |_, index| index > 0);
+
+        match node {
+            Some((node, index)) => {
+                self.children_mut(self.nodes[node].parent)
+                    .swap(index, index - 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inverse of [`Self::promote_variation`].
+    pub fn demote_variation(&mut self, id: NodeId) -> bool {
+        let node = self.find_ancestor(id, |siblings, index| index + 1 < siblings.len());
+
+        match node {
+            Some((node, index)) => {
+                self.children_mut(self.nodes[node].parent)
+                    .swap(index, index + 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the node with every move after it. If the shown position is
+    /// removed, the position before the node is shown instead.
+    pub fn delete_from(&mut self, id: NodeId) {
+        let parent = self.nodes[id].parent;
+        let index_in_line = self.line.iter().position(|node| *node == id);
+
+        self.children_mut(parent).retain(|child| *child != id);
+
+        match index_in_line {
+            Some(index) if self.current > index => match parent {
+                Some(parent) => self.go_to_node(parent),
+                None => {
+                    self.line.clear();
+                    self.current = 0;
+                    self.extend_line();
+                }
+            },
+            Some(index) => {
+                self.line.truncate(index);
+                self.extend_line();
+            }
+            None => (),
+        }
+    }
+
+    /// Walks from the node towards the start and returns the first node
+    /// (with its index among its siblings) for which `is_match` holds.
+    fn find_ancestor(
+        &self,
+        id: NodeId,
+        is_match: impl Fn(&[NodeId], usize) -> bool,
+    ) -> Option<(NodeId, usize)> {
+        let mut node = Some(id);
+
+        while let Some(id) = node {
+            let siblings = self.children(self.nodes[id].parent);
+            let index = siblings.iter().position(|sibling| *sibling == id)?;
+
+            if is_match(siblings, index) {
+                return Some((id, index));
+            }
+
+            node = self.nodes[id].parent;
+        }
+
+        None
+    }
+
+    fn children_mut(&mut self, id: Option<NodeId>) -> &mut Vec<NodeId> {
+        match id {
+            Some(id) => &mut self.nodes[id].children,
+            None => &mut self.roots,
+        }
+    }
+
+    /// Continues the line with the first continuation of its last position
+    /// until there is none.
+    fn extend_line(&mut self) {
+        while let Some(next) = self.children(self.line.last().copied()).first().copied() {
+            self.line.push(next);
+        }
+    }
+}
+
+fn get_promotion(before: &Board, after: &Board, from: Pos, to: Pos) -> Option<Piece> {
+    let moved = before.get(from.0, from.1)?;
+    let arrived = after.get(to.0, to.1)?;
+
+    let is_promotion = matches!(moved.piece, Piece::Pawn) && !matches!(arrived.piece, Piece::Pawn);
+
+    if is_promotion {
+        Some(arrived.piece)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_history() -> History {
+        let (board, to_move) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+
+        History::new(board, to_move)
+    }
+
+    /// Plays the moves, given in SAN, from the current position.
+    fn play(history: &mut History, sans: &[&str]) {
+        for san in sans {
+            let index = history.current();
+            let before = history.board_at(index).clone();
+            let player = history.player_to_move_at(index);
+            let mv = notation::parse_san(&before, &player, san).unwrap();
+            let after = rules::apply_move(&before, &mv).unwrap();
+
+            history.push(&before, &after, mv.from, mv.to);
+        }
+    }
+
+    fn line_sans(history: &History) -> Vec<&str> {
+        history.plies().iter().map(|ply| ply.san.as_str()).collect()
+    }
+
+    #[test]
+    fn other_move_adds_variation() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5", "Nf3"]);
+        history.go_to(1);
+        play(&mut history, &["c5"]);
+
+        assert_eq!(line_sans(&history), ["e4", "c5"]);
+        assert_eq!(history.children(Some(history.line()[0])).len(), 2);
+
+        let e5 = history.children(Some(history.line()[0]))[0];
+        history.go_to_node(e5);
+
+        assert_eq!(line_sans(&history), ["e4", "e5", "Nf3"]);
+        assert_eq!(history.current(), 2);
+    }
+
+    #[test]
+    fn same_move_reuses_node() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5"]);
+        assert!(history.undo());
+        play(&mut history, &["e5"]);
+
+        assert_eq!(history.children(Some(history.line()[0])).len(), 1);
+        assert_eq!(line_sans(&history), ["e4", "e5"]);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn promote_and_demote_variation() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5"]);
+        history.go_to(1);
+        play(&mut history, &["c5", "Nf3"]);
+
+        let e4 = history.line()[0];
+        let (c5, nf3) = (history.line()[1], history.line()[2]);
+
+        // Promoting works from any move of the variation.
+        assert!(history.promote_variation(nf3));
+        assert_eq!(history.children(Some(e4))[0], c5);
+        assert!(!history.promote_variation(c5));
+
+        assert!(history.demote_variation(c5));
+        assert_eq!(history.children(Some(e4))[1], c5);
+        assert!(!history.demote_variation(c5));
+    }
+
+    #[test]
+    fn delete_shown_move() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5", "Nf3"]);
+
+        let (e4, e5, nf3) = (history.line()[0], history.line()[1], history.line()[2]);
+        history.delete_from(e5);
+
+        assert_eq!(line_sans(&history), ["e4"]);
+        assert_eq!(history.current(), 1);
+        assert!(history.contains(e4));
+        assert!(!history.contains(e5));
+        assert!(!history.contains(nf3));
+    }
+
+    #[test]
+    fn delete_move_after_shown_position() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5", "Nf3"]);
+        history.go_to(1);
+
+        let nf3 = history.line()[2];
+        history.delete_from(nf3);
+
+        assert_eq!(line_sans(&history), ["e4", "e5"]);
+        assert_eq!(history.current(), 1);
+    }
+
+    #[test]
+    fn delete_variation_keeps_line() {
+        let mut history = new_history();
+
+        play(&mut history, &["e4", "e5"]);
+        history.go_to(1);
+        play(&mut history, &["c5"]);
+
+        let c5 = history.line()[1];
+        let e5 = history.children(Some(history.line()[0]))[0];
+        history.go_to_node(e5);
+        history.delete_from(c5);
+
+        assert_eq!(line_sans(&history), ["e4", "e5"]);
+        assert_eq!(history.children(Some(history.line()[0])), [e5]);
+    }
+}
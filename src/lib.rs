@@ -6,11 +6,29 @@ use eframe::wasm_bindgen::{self, prelude::*};
 use linked_hash_map::LinkedHashMap;
 use yaml_rust::Yaml;
 
+pub mod annotations;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cecp;
 pub mod chess_client;
+pub mod clock;
 mod deserialize;
 pub use deserialize::deserialize_game;
+pub mod engine;
+#[cfg(not(target_arch = "wasm32"))]
+mod engine_process;
+mod game;
+pub use game::Game;
+pub mod history;
+pub mod material;
+pub mod notation;
+pub mod outcome;
+pub mod pgn;
+pub mod review;
+pub mod rules;
 mod serialize;
 pub use serialize::serialize_game;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod uci;
 
 /// This is the entry-point for all the web-assembly.
 /// This is called once from the HTML.
@@ -25,7 +43,8 @@ pub fn start(canvas_id: &str) -> Result<(), eframe::wasm_bindgen::JsValue> {
     // Redirect tracing to console.log and friends:
     tracing_wasm::set_as_global_default();
 
-    let app = ChessClient::default();
-    eframe::start_web(canvas_id, Box::new(|_| Box::new(app)))
+    eframe::start_web(
+        canvas_id,
+        Box::new(|cc| Box::new(chess_client::ChessClient::new(cc))),
+    )
 }
-
@@ -0,0 +1,203 @@
+use chess_logic::{Board, Piece, Player};
+
+use crate::{history::History, rules};
+
+/// Conventional value of a piece in pawns. The king has no material value.
+pub fn piece_value(piece: &Piece) -> i32 {
+    match piece {
+        Piece::Bishop => 3,
+        Piece::King => 0,
+        Piece::Knight => 3,
+        Piece::Pawn => 1,
+        Piece::Queen => 9,
+        Piece::Rook => 5,
+    }
+}
+
+/// Material of `player` minus the material of the opponent.
+pub fn material_balance(board: &Board, player: &Player) -> i32 {
+    let mut balance = 0;
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if let Some(ins) = board.get(x, y) {
+                match &ins.player == player {
+                    true => balance += piece_value(&ins.piece),
+                    false => balance -= piece_value(&ins.piece),
+                }
+            }
+        }
+    }
+
+    balance
+}
+
+/// Returns the pieces `player` captured up to the current position of the
+/// history, most valuable first.
+///
+/// Captures are found by comparing the opponent's pieces before and after each
+/// move of the player, which also covers en passant. Promotions only change
+/// the mover's own pieces and are therefore never counted.
+pub fn captured_by(history: &History, player: &Player) -> Vec<Piece> {
+    let opponent = rules::opponent_of(player);
+    let mut captured = Vec::new();
+
+    for (index, ply) in history.plies()[..history.current()].iter().enumerate() {
+        if &ply.player != player {
+            continue;
+        }
+
+        let before = count_pieces_of(history.board_at(index), &opponent);
+        let after = count_pieces_of(&ply.board, &opponent);
+
+        for (piece, (before, after)) in PIECES.iter().zip(before.iter().zip(after.iter())) {
+            for _ in *after..*before {
+                captured.push(*piece);
+            }
+        }
+    }
+
+    captured.sort_by_key(|piece| -piece_value(piece));
+
+    captured
+}
+
+const PIECES: [Piece; 6] = [
+    Piece::Bishop,
+    Piece::King,
+    Piece::Knight,
+    Piece::Pawn,
+    Piece::Queen,
+    Piece::Rook,
+];
+
+/// Number of pieces of each kind, in the order of [`PIECES`].
+fn count_pieces_of(board: &Board, player: &Player) -> [usize; 6] {
+    let mut counts = [0; 6];
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if let Some(ins) = board.get(x, y) {
+                if &ins.player == player {
+                    let index = PIECES
+                        .iter()
+                        .position(|piece| rules::is_same_piece(piece, &ins.piece))
+                        .unwrap();
+
+                    counts[index] += 1;
+                }
+            }
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+
+    fn new_history(fen: &str) -> History {
+        let (board, to_move) = notation::parse_fen(fen).unwrap();
+
+        History::new(board, to_move)
+    }
+
+    /// Plays the moves, given in SAN, from the current position.
+    fn play(history: &mut History, sans: &[&str]) {
+        for san in sans {
+            let index = history.current();
+            let before = history.board_at(index).clone();
+            let player = history.player_to_move_at(index);
+            let mv = notation::parse_san(&before, &player, san).unwrap();
+            let after = rules::apply_move(&before, &mv).unwrap();
+
+            history.push(&before, &after, mv.from, mv.to);
+        }
+    }
+
+    /// Names of the pieces the player whose turn it was at the start
+    /// captured, and of those the opponent captured.
+    fn captured(history: &History) -> (Vec<String>, Vec<String>) {
+        let player = history.start_player().clone();
+        let names = |player: &Player| {
+            captured_by(history, player)
+                .iter()
+                .map(|piece| format!("{:?}", piece))
+                .collect()
+        };
+
+        (names(&player), names(&rules::opponent_of(&player)))
+    }
+
+    fn balance(history: &History) -> i32 {
+        material_balance(history.board_at(history.current()), history.start_player())
+    }
+
+    #[test]
+    fn counts_captures_of_both_players() {
+        let mut history = new_history(notation::STARTING_FEN);
+
+        play(
+            &mut history,
+            &["e4", "d5", "exd5", "Qxd5", "Nc3", "Qxa2", "Rxa2"],
+        );
+
+        assert_eq!(
+            captured(&history),
+            (
+                vec!["Queen".into(), "Pawn".into()],
+                vec!["Pawn".into(), "Pawn".into()]
+            )
+        );
+        assert_eq!(balance(&history), 8);
+
+        // Only the moves up to the current position count.
+        history.go_to(3);
+
+        assert_eq!(captured(&history), (vec!["Pawn".into()], vec![]));
+        assert_eq!(balance(&history), 1);
+    }
+
+    #[test]
+    fn counts_en_passant_captures() {
+        let mut history = new_history("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1");
+
+        play(&mut history, &["e4", "dxe3"]);
+
+        assert_eq!(captured(&history), (vec![], vec!["Pawn".into()]));
+        assert_eq!(balance(&history), -1);
+    }
+
+    #[test]
+    fn promotions_change_the_balance_but_capture_nothing() {
+        let mut history = new_history("8/1P6/8/7k/8/8/6p1/4K3 w - - 0 1");
+
+        assert_eq!(balance(&history), 0);
+
+        play(&mut history, &["b8=Q", "g1=N"]);
+
+        assert_eq!(captured(&history), (vec![], vec![]));
+        assert_eq!(balance(&history), 6);
+    }
+
+    #[test]
+    fn capturing_promotions_count_the_captured_piece() {
+        let mut history = new_history("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+
+        play(&mut history, &["bxa8=Q"]);
+
+        assert_eq!(captured(&history), (vec!["Rook".into()], vec![]));
+        assert_eq!(balance(&history), 9);
+    }
+
+    #[test]
+    fn piece_values() {
+        let (board, white) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+
+        assert_eq!(material_balance(&board, &white), 0);
+        assert_eq!(piece_value(&Piece::Queen), 9);
+        assert_eq!(piece_value(&Piece::King), 0);
+    }
+}
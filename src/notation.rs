@@ -0,0 +1,633 @@
+use chess_logic::{board::PieceInstance, Board, Color, Piece, Player};
+
+use crate::rules::{self, is_same_piece, Move, Pos};
+
+/// FEN of the standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Returns the algebraic name of a square (e.g. `"e4"`).
+///
+/// The board stores "you" at the bottom, so the files and ranks depend on the
+/// color "you" are playing.
+pub fn square_name(board: &Board, pos: Pos) -> String {
+    let (file, rank) = file_and_rank(board, pos);
+
+    format!("{}{}", (b'a' + file as u8) as char, rank)
+}
+
+/// File (`0` for the a-file) and rank (`1` to `8`) of the square.
+pub fn file_and_rank(board: &Board, (x, y): Pos) -> (i8, i8) {
+    match board.get_color_of_player(&Player::You) {
+        Color::White => (x, board.height() - y),
+        Color::Black => (board.width() - 1 - x, y + 1),
+    }
+}
+
+/// Inverse of [`square_name`].
+pub fn parse_square(board: &Board, name: &str) -> Option<Pos> {
+    let mut chars = name.chars();
+
+    let file = chars.next()?;
+    let rank = chars.as_str().parse::<i8>().ok()?;
+
+    if !('a'..='h').contains(&file) {
+        return None;
+    }
+
+    let file = (file as u8 - b'a') as i8;
+
+    let pos = match board.get_color_of_player(&Player::You) {
+        Color::White => (file, board.height() - rank),
+        Color::Black => (board.width() - 1 - file, rank - 1),
+    };
+
+    if pos.0 < 0 || pos.0 >= board.width() || pos.1 < 0 || pos.1 >= board.height() {
+        return None;
+    }
+
+    Some(pos)
+}
+
+/// Letter of a piece as used in FEN and SAN (always upper case).
+pub fn piece_letter(piece: &Piece) -> char {
+    match piece {
+        Piece::Bishop => 'B',
+        Piece::King => 'K',
+        Piece::Knight => 'N',
+        Piece::Pawn => 'P',
+        Piece::Queen => 'Q',
+        Piece::Rook => 'R',
+    }
+}
+
+pub fn parse_piece_letter(letter: char) -> Option<Piece> {
+    Some(match letter.to_ascii_uppercase() {
+        'B' => Piece::Bishop,
+        'K' => Piece::King,
+        'N' => Piece::Knight,
+        'P' => Piece::Pawn,
+        'Q' => Piece::Queen,
+        'R' => Piece::Rook,
+        _ => return None,
+    })
+}
+
+/// Describes the piece placement like the first field of a FEN, always from
+/// white's point of view. Equal positions produce equal keys.
+pub fn position_key(board: &Board) -> String {
+    let mut key = String::new();
+
+    for rank in (1..=board.height()).rev() {
+        let mut empty_squares = 0;
+
+        for file in 0..board.width() {
+            let name = format!("{}{}", (b'a' + file as u8) as char, rank);
+            let (x, y) = parse_square(board, &name).unwrap();
+
+            match board.get(x, y) {
+                Some(ins) => {
+                    if empty_squares > 0 {
+                        key.push_str(&empty_squares.to_string());
+                        empty_squares = 0;
+                    }
+
+                    let letter = piece_letter(&ins.piece);
+
+                    key.push(match board.get_color_of_player(&ins.player) {
+                        Color::White => letter,
+                        Color::Black => letter.to_ascii_lowercase(),
+                    });
+                }
+                None => empty_squares += 1,
+            }
+        }
+
+        if empty_squares > 0 {
+            key.push_str(&empty_squares.to_string());
+        }
+
+        if rank > 1 {
+            key.push('/');
+        }
+    }
+
+    key
+}
+
+/// Full FEN of the position with `to_move` being the player whose turn it
+/// is. Castling rights are derived from the `was_moved` flags of the kings
+/// and rooks. The move counters are not tracked, so they are always `0 1`.
+pub fn format_fen(board: &Board, to_move: &Player) -> String {
+    let side = match board.get_color_of_player(to_move) {
+        Color::White => "w",
+        Color::Black => "b",
+    };
+
+    format!(
+        "{} {} {} {} 0 1",
+        position_key(board),
+        side,
+        get_fen_castling(board),
+        get_fen_en_passant(board)
+    )
+}
+
+/// Castling field of the FEN, e.g. `KQkq`, derived from the `was_moved`
+/// flags of the kings and rooks on their starting squares.
+pub fn get_fen_castling(board: &Board) -> String {
+    let is_unmoved = |square: &str, piece: Piece, color: Color| {
+        let (x, y) = match parse_square(board, square) {
+            Some(pos) => pos,
+            None => return false,
+        };
+
+        match board.get(x, y) {
+            Some(ins) => {
+                !ins.was_moved
+                    && is_same_piece(&ins.piece, &piece)
+                    && rules::is_same_color(board.get_color_of_player(&ins.player), &color)
+            }
+            None => false,
+        }
+    };
+
+    let mut castling = String::new();
+
+    for (king, rooks, color) in [
+        ("e1", [("h1", 'K'), ("a1", 'Q')], Color::White),
+        ("e8", [("h8", 'k'), ("a8", 'q')], Color::Black),
+    ] {
+        if !is_unmoved(king, Piece::King, color.clone()) {
+            continue;
+        }
+
+        for (rook, letter) in rooks {
+            if is_unmoved(rook, Piece::Rook, color.clone()) {
+                castling.push(letter);
+            }
+        }
+    }
+
+    match castling.is_empty() {
+        true => "-".to_owned(),
+        false => castling,
+    }
+}
+
+/// Square behind the pawn that just made a double step, or `-`.
+fn get_fen_en_passant(board: &Board) -> String {
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let ins = match board.get(x, y) {
+                Some(ins) if ins.is_eligible_for_en_passant => ins,
+                _ => continue,
+            };
+
+            let name = square_name(board, (x, y));
+            let behind = match board.get_color_of_player(&ins.player) {
+                Color::White => '3',
+                Color::Black => '6',
+            };
+
+            return format!("{}{}", &name[..1], behind);
+        }
+    }
+
+    "-".to_owned()
+}
+
+/// Inverse of [`format_fen`], with white at the bottom of the board. The
+/// castling rights and the en passant square are turned into the
+/// `was_moved` and `is_eligible_for_en_passant` flags, the move counters are
+/// ignored.
+pub fn parse_fen(fen: &str) -> Option<(Board, Player)> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let mut board = Board::new(Color::White, Color::Black);
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+
+    if ranks.len() != board.height() as usize {
+        return None;
+    }
+
+    for (row, rank) in ranks.iter().enumerate() {
+        let mut file = 0;
+
+        for letter in rank.chars() {
+            if let Some(empty_squares) = letter.to_digit(10) {
+                file += empty_squares as i8;
+
+                if file > board.width() {
+                    return None;
+                }
+
+                continue;
+            }
+
+            if file >= board.width() {
+                return None;
+            }
+
+            let name = format!(
+                "{}{}",
+                (b'a' + file as u8) as char,
+                board.height() as usize - row
+            );
+            let (x, y) = parse_square(&board, &name)?;
+            let player = match letter.is_ascii_uppercase() {
+                true => Player::You,
+                false => Player::Opponent,
+            };
+
+            board.set(
+                x,
+                y,
+                Some(PieceInstance::new(player, parse_piece_letter(letter)?)),
+            );
+            file += 1;
+        }
+
+        if file != board.width() {
+            return None;
+        }
+    }
+
+    let castling = fields[2];
+    let en_passant_pawn = match fields[3] {
+        "-" => None,
+        square if !square.is_ascii() => return None,
+        square => match square.split_at(1) {
+            (file, "3") => Some(format!("{}4", file)),
+            (file, "6") => Some(format!("{}5", file)),
+            _ => return None,
+        },
+    };
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let mut ins = match board.get(x, y) {
+                Some(ins) => ins.clone(),
+                None => continue,
+            };
+
+            let square = square_name(&board, (x, y));
+            let (home_rank, pawn_rank, king_side, queen_side) = match ins.player {
+                Player::You => ('1', '2', 'K', 'Q'),
+                Player::Opponent => ('8', '7', 'k', 'q'),
+            };
+            let is_on_home_rank = square.ends_with(home_rank);
+
+            ins.was_moved = match ins.piece {
+                Piece::King => {
+                    !(is_on_home_rank
+                        && square.starts_with('e')
+                        && (castling.contains(king_side) || castling.contains(queen_side)))
+                }
+                Piece::Rook if is_on_home_rank && square.starts_with('h') => {
+                    !castling.contains(king_side)
+                }
+                Piece::Rook if is_on_home_rank && square.starts_with('a') => {
+                    !castling.contains(queen_side)
+                }
+                Piece::Pawn => !square.ends_with(pawn_rank),
+                _ => true,
+            };
+            ins.is_eligible_for_en_passant = en_passant_pawn.as_ref() == Some(&square);
+
+            board.set(x, y, Some(ins));
+        }
+    }
+
+    let to_move = match fields[1] {
+        "w" => Player::You,
+        "b" => Player::Opponent,
+        _ => return None,
+    };
+
+    Some((board, to_move))
+}
+
+/// Formats a move in UCI notation, e.g. `"e2e4"` or `"e7e8q"`.
+pub fn format_uci(board: &Board, mv: &Move) -> String {
+    let mut uci = format!(
+        "{}{}",
+        square_name(board, mv.from),
+        square_name(board, mv.to)
+    );
+
+    if let Some(promotion) = &mv.promotion {
+        uci.push(piece_letter(promotion).to_ascii_lowercase());
+    }
+
+    uci
+}
+
+pub fn parse_uci(board: &Board, uci: &str) -> Option<Move> {
+    if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+        return None;
+    }
+
+    let from = parse_square(board, &uci[0..2])?;
+    let to = parse_square(board, &uci[2..4])?;
+    let promotion = match uci[4..].chars().next() {
+        Some(letter) => Some(parse_piece_letter(letter)?),
+        None => None,
+    };
+
+    Some(Move {
+        from,
+        promotion,
+        to,
+    })
+}
+
+/// Formats a move of `player` in standard algebraic notation, e.g. `"Nbd7"`,
+/// `"exd5"`, `"e8=Q+"` or `"O-O"`. The board is the position before the move.
+pub fn format_san(board: &Board, player: &Player, mv: &Move) -> String {
+    let ins = match board.get(mv.from.0, mv.from.1) {
+        Some(ins) => ins,
+        None => return format_uci(board, mv),
+    };
+
+    let is_capture = board.get(mv.to.0, mv.to.1).is_some()
+        || (matches!(ins.piece, Piece::Pawn) && mv.from.0 != mv.to.0);
+
+    let mut san = String::new();
+
+    if is_castling(board, mv) {
+        // Castling towards the a-file is the long one.
+        let to_a_file = square_name(board, mv.to).starts_with('c');
+
+        san.push_str(if to_a_file { "O-O-O" } else { "O-O" });
+    } else if matches!(ins.piece, Piece::Pawn) {
+        if is_capture {
+            san.push(square_name(board, mv.from).chars().next().unwrap());
+            san.push('x');
+        }
+
+        san.push_str(&square_name(board, mv.to));
+
+        if let Some(promotion) = &mv.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+    } else {
+        san.push(piece_letter(&ins.piece));
+        san.push_str(&get_disambiguation(board, player, mv));
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&square_name(board, mv.to));
+    }
+
+    if let Some(after) = rules::apply_move(board, mv) {
+        let opponent = rules::opponent_of(player);
+
+        if rules::is_in_check(&after, &opponent) {
+            let is_mate = rules::legal_moves(&after, &opponent).is_empty();
+
+            san.push(if is_mate { '#' } else { '+' });
+        }
+    }
+
+    san
+}
+
+/// Returns the file, rank or both of the origin square, if another piece of
+/// the same kind could also reach the destination.
+fn get_disambiguation(board: &Board, player: &Player, mv: &Move) -> String {
+    let piece = board.get(mv.from.0, mv.from.1).unwrap().piece;
+
+    let others: Vec<Pos> = rules::legal_moves(board, player)
+        .into_iter()
+        .filter(|other| other.to == mv.to && other.from != mv.from)
+        .filter(|other| match board.get(other.from.0, other.from.1) {
+            Some(ins) => is_same_piece(&ins.piece, &piece),
+            None => false,
+        })
+        .map(|other| other.from)
+        .collect();
+
+    let from_name = square_name(board, mv.from);
+    let (file, rank) = from_name.split_at(1);
+
+    if others.is_empty() {
+        String::new()
+    } else if others
+        .iter()
+        .all(|other| !square_name(board, *other).starts_with(file))
+    {
+        file.to_owned()
+    } else if others
+        .iter()
+        .all(|other| !square_name(board, *other).ends_with(rank))
+    {
+        rank.to_owned()
+    } else {
+        from_name
+    }
+}
+
+/// Parses a move of `player` in standard algebraic notation. Check, mate and
+/// annotation symbols are ignored, captures do not have to be marked.
+pub fn parse_san(board: &Board, player: &Player, san: &str) -> Option<Move> {
+    let san = san.trim_end_matches(|c| matches!(c, '+' | '#' | '!' | '?'));
+    let legal_moves = rules::legal_moves(board, player);
+
+    // Some programs write castling with zeros.
+    let castling = match san {
+        "O-O" | "0-0" => Some("g"),
+        "O-O-O" | "0-0-0" => Some("c"),
+        _ => None,
+    };
+
+    if let Some(file) = castling {
+        return legal_moves
+            .into_iter()
+            .find(|mv| is_castling(board, mv) && square_name(board, mv.to).starts_with(file));
+    }
+
+    let (san, promotion) = match san.split_once('=') {
+        Some((san, promotion)) => (san, Some(parse_piece_letter(promotion.chars().next()?)?)),
+        None => (san, None),
+    };
+
+    let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+
+    let piece = match chars.first() {
+        Some(c) if c.is_ascii_uppercase() => {
+            let piece = parse_piece_letter(*c)?;
+            chars.remove(0);
+            piece
+        }
+        _ => Piece::Pawn,
+    };
+
+    if chars.len() < 2 {
+        return None;
+    }
+
+    let to_name: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = parse_square(board, &to_name)?;
+    let from_hint: String = chars.into_iter().collect();
+
+    let mut candidates = legal_moves.into_iter().filter(|mv| {
+        let is_same_kind = match board.get(mv.from.0, mv.from.1) {
+            Some(ins) => is_same_piece(&ins.piece, &piece),
+            None => false,
+        };
+        let is_same_promotion = match (&mv.promotion, &promotion) {
+            (Some(a), Some(b)) => is_same_piece(a, b),
+            (Some(a), None) => matches!(a, Piece::Queen),
+            (None, None) => true,
+            (None, Some(_)) => false,
+        };
+
+        is_same_kind
+            && is_same_promotion
+            && mv.to == to
+            && from_hint
+                .chars()
+                .all(|c| square_name(board, mv.from).contains(c))
+    });
+
+    let mv = candidates.next()?;
+
+    // Ambiguous moves are rejected instead of guessed.
+    if candidates.next().is_some() {
+        return None;
+    }
+
+    Some(mv)
+}
+
+/// Accepts both UCI and SAN.
+pub fn parse_move(board: &Board, player: &Player, text: &str) -> Option<Move> {
+    let text = text.trim();
+
+    parse_uci(board, text)
+        .filter(|mv| rules::legal_moves(board, player).contains(mv))
+        .or_else(|| parse_san(board, player, text))
+}
+
+fn is_castling(board: &Board, mv: &Move) -> bool {
+    let is_king = match board.get(mv.from.0, mv.from.1) {
+        Some(ins) => matches!(ins.piece, Piece::King),
+        None => false,
+    };
+
+    is_king && (mv.from.0 - mv.to.0).abs() == 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Positions with castling rights, en passant, promotions and pieces
+    /// that need disambiguation.
+    const FENS: [&str; 5] = [
+        STARTING_FEN,
+        "r3k2r/pppq1ppp/2n5/3pP3/8/2N5/PPPQ1PPP/R3K2R w Kq d6 0 1",
+        "4k3/8/8/8/8/2N3N1/8/4K3 w - - 0 1",
+        "1r2k3/2P5/8/8/8/8/1p6/4K2R b K - 0 1",
+        "8/8/8/8/8/8/8/4K2k b - - 0 1",
+    ];
+
+    #[test]
+    fn fen_round_trip() {
+        for fen in FENS {
+            let (board, to_move) = parse_fen(fen).unwrap();
+
+            assert_eq!(format_fen(&board, &to_move), fen);
+        }
+    }
+
+    #[test]
+    fn invalid_fen_is_rejected() {
+        assert!(parse_fen("8/8/8 w - - 0 1").is_none());
+        assert!(parse_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_none());
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").is_none());
+        // Ranks running past the board must not reach past the file letters.
+        assert!(parse_fen("rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").is_none());
+        assert!(parse_fen("99999999999999/8/8/8/8/8/8/8 w - - 0 1").is_none());
+    }
+
+    #[test]
+    fn square_round_trip_for_both_sides() {
+        for board in [
+            Board::new(Color::White, Color::Black),
+            Board::new(Color::Black, Color::White),
+        ] {
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    assert_eq!(
+                        parse_square(&board, &square_name(&board, (x, y))),
+                        Some((x, y))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn san_round_trip() {
+        for fen in FENS {
+            let (board, to_move) = parse_fen(fen).unwrap();
+
+            for mv in rules::legal_moves(&board, &to_move) {
+                let san = format_san(&board, &to_move, &mv);
+
+                assert_eq!(
+                    parse_san(&board, &to_move, &san),
+                    Some(mv),
+                    "{} in {}",
+                    san,
+                    fen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn uci_round_trip() {
+        for fen in FENS {
+            let (board, to_move) = parse_fen(fen).unwrap();
+
+            for mv in rules::legal_moves(&board, &to_move) {
+                let uci = format_uci(&board, &mv);
+
+                assert_eq!(parse_uci(&board, &uci), Some(mv), "{} in {}", uci, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn san_of_special_moves() {
+        let (board, to_move) = parse_fen(FENS[1]).unwrap();
+        let castling = parse_uci(&board, "e1g1").unwrap();
+        let en_passant = parse_uci(&board, "e5d6").unwrap();
+
+        assert_eq!(format_san(&board, &to_move, &castling), "O-O");
+        assert_eq!(format_san(&board, &to_move, &en_passant), "exd6");
+        assert_eq!(parse_san(&board, &to_move, "0-0"), Some(castling));
+        assert_eq!(parse_san(&board, &to_move, "O-O-O"), None);
+
+        let (board, to_move) = parse_fen(FENS[2]).unwrap();
+        let knight_move = parse_uci(&board, "c3e4").unwrap();
+
+        assert_eq!(format_san(&board, &to_move, &knight_move), "Nce4");
+        assert_eq!(parse_san(&board, &to_move, "Ne4"), None);
+
+        let (board, to_move) = parse_fen(FENS[3]).unwrap();
+        let promotion = parse_uci(&board, "b2b1n").unwrap();
+
+        assert_eq!(format_san(&board, &to_move, &promotion), "b1=N");
+        assert_eq!(parse_san(&board, &to_move, "b1=N"), Some(promotion));
+    }
+}
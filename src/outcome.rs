@@ -0,0 +1,371 @@
+use chess_logic::{Board, Color, Piece, Player};
+
+use crate::{history::History, notation, rules};
+
+/// Number of plies without capture or pawn move after which the game is
+/// drawn.
+const FIFTY_MOVE_RULE_PLIES: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    Stalemate,
+    ThreefoldRepetition,
+    Timeout,
+    /// The flag fell, but the opponent could not have mated anymore.
+    TimeoutVsInsufficientMaterial,
+}
+
+impl Termination {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Checkmate => "Checkmate",
+            Self::FiftyMoveRule => "Fifty-move rule",
+            Self::InsufficientMaterial => "Insufficient material",
+            Self::Stalemate => "Stalemate",
+            Self::ThreefoldRepetition => "Threefold repetition",
+            Self::Timeout => "Time forfeit",
+            Self::TimeoutVsInsufficientMaterial => "Time forfeit vs. insufficient material",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub termination: Termination,
+    /// `None` for draws.
+    pub winner: Option<Player>,
+}
+
+impl GameResult {
+    /// The result as written in PGN: `"1-0"`, `"0-1"` or `"1/2-1/2"`.
+    pub fn pgn_result(&self, board: &Board) -> &'static str {
+        match &self.winner {
+            Some(winner) => match board.get_color_of_player(winner) {
+                Color::White => "1-0",
+                Color::Black => "0-1",
+            },
+            None => "1/2-1/2",
+        }
+    }
+
+    /// Human readable result, e.g. `"Checkmate, White wins"`.
+    pub fn description(&self, board: &Board) -> String {
+        match &self.winner {
+            Some(winner) => format!(
+                "{}, {:?} wins",
+                self.termination.description(),
+                board.get_color_of_player(winner)
+            ),
+            None => format!("{}, draw", self.termination.description()),
+        }
+    }
+}
+
+/// Checks whether the game has ended in the current position of the history,
+/// with `to_move` being the player whose turn it is.
+pub fn detect_result(history: &History, to_move: &Player) -> Option<GameResult> {
+    let board = history.board_at(history.current());
+
+    if rules::legal_moves(board, to_move).is_empty() {
+        return Some(match rules::is_in_check(board, to_move) {
+            true => GameResult {
+                termination: Termination::Checkmate,
+                winner: Some(rules::opponent_of(to_move)),
+            },
+            false => draw(Termination::Stalemate),
+        });
+    }
+
+    if is_dead_position(board) {
+        return Some(draw(Termination::InsufficientMaterial));
+    }
+
+    if count_plies_since_progress(history) >= FIFTY_MOVE_RULE_PLIES {
+        return Some(draw(Termination::FiftyMoveRule));
+    }
+
+    if count_repetitions(history) >= 3 {
+        return Some(draw(Termination::ThreefoldRepetition));
+    }
+
+    None
+}
+
+/// Result after the flag of `player` fell.
+pub fn timeout_result(board: &Board, player: &Player) -> GameResult {
+    let opponent = rules::opponent_of(player);
+
+    if has_mating_material(board, &opponent) {
+        GameResult {
+            termination: Termination::Timeout,
+            winner: Some(opponent),
+        }
+    } else {
+        draw(Termination::TimeoutVsInsufficientMaterial)
+    }
+}
+
+fn draw(termination: Termination) -> GameResult {
+    GameResult {
+        termination,
+        winner: None,
+    }
+}
+
+/// Checks whether neither player can mate anymore, whatever is played: king
+/// against king, king and minor piece against king, or only bishops on
+/// squares of one color. Unlike [`has_mating_material`], this considers the
+/// pieces of both players, as e.g. a knight on each side can still help the
+/// opponent mate.
+fn is_dead_position(board: &Board) -> bool {
+    let mut minor_pieces = 0;
+    let mut has_knight = false;
+    let mut bishop_square_colors = Vec::new();
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let ins = match board.get(x, y) {
+                Some(ins) => ins,
+                None => continue,
+            };
+
+            match ins.piece {
+                Piece::King => continue,
+                Piece::Knight => has_knight = true,
+                Piece::Bishop => bishop_square_colors.push((x + y) % 2),
+                Piece::Pawn | Piece::Queen | Piece::Rook => return false,
+            }
+
+            minor_pieces += 1;
+        }
+    }
+
+    let bishops_on_one_color =
+        !(bishop_square_colors.contains(&0) && bishop_square_colors.contains(&1));
+
+    minor_pieces <= 1 || (!has_knight && bishops_on_one_color)
+}
+
+/// Checks whether the player has enough pieces to possibly mate. A lone king,
+/// king and minor piece, or only bishops on squares of one color can't.
+pub fn has_mating_material(board: &Board, player: &Player) -> bool {
+    let mut knights = 0;
+    let mut bishop_square_colors = Vec::new();
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let ins = match board.get(x, y) {
+                Some(ins) if &ins.player == player => ins,
+                _ => continue,
+            };
+
+            match ins.piece {
+                Piece::King => (),
+                Piece::Knight => knights += 1,
+                Piece::Bishop => bishop_square_colors.push((x + y) % 2),
+                Piece::Pawn | Piece::Queen | Piece::Rook => return true,
+            }
+        }
+    }
+
+    let bishops_on_both_colors =
+        bishop_square_colors.contains(&0) && bishop_square_colors.contains(&1);
+
+    match (knights, bishop_square_colors.len()) {
+        (0, _) => bishops_on_both_colors,
+        (1, 0) => false,
+        _ => true,
+    }
+}
+
+/// Counts the plies up to the current position since the last capture or
+/// pawn move.
+fn count_plies_since_progress(history: &History) -> usize {
+    let mut count = 0;
+
+    for index in (1..=history.current()).rev() {
+        let before = history.board_at(index - 1);
+        let after = history.board_at(index);
+        let mv = history.move_to(index).unwrap();
+
+        let is_pawn_move = match before.get(mv.from.0, mv.from.1) {
+            Some(ins) => matches!(ins.piece, Piece::Pawn),
+            None => false,
+        };
+
+        if is_pawn_move || count_pieces(after) < count_pieces(before) {
+            break;
+        }
+
+        count += 1;
+    }
+
+    count
+}
+
+fn count_pieces(board: &Board) -> usize {
+    (0..board.height())
+        .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+        .filter(|(x, y)| board.get(*x, *y).is_some())
+        .count()
+}
+
+/// How often the current position occurred with the same player to move.
+fn count_repetitions(history: &History) -> usize {
+    let repetition_key = |index: usize| {
+        (
+            notation::position_key(history.board_at(index)),
+            format!("{:?}", history.player_to_move_at(index)),
+        )
+    };
+
+    let current_key = repetition_key(history.current());
+
+    (0..=history.current())
+        .filter(|index| repetition_key(*index) == current_key)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_history(fen: &str) -> History {
+        let (board, to_move) = notation::parse_fen(fen).unwrap();
+
+        History::new(board, to_move)
+    }
+
+    /// Plays the moves, given in SAN, from the current position.
+    fn play(history: &mut History, sans: &[&str]) {
+        for san in sans {
+            let index = history.current();
+            let before = history.board_at(index).clone();
+            let player = history.player_to_move_at(index);
+            let mv = notation::parse_san(&before, &player, san).unwrap();
+            let after = rules::apply_move(&before, &mv).unwrap();
+
+            history.push(&before, &after, mv.from, mv.to);
+        }
+    }
+
+    fn termination(history: &History) -> Option<Termination> {
+        let to_move = history.player_to_move_at(history.current());
+
+        detect_result(history, &to_move).map(|result| result.termination)
+    }
+
+    fn termination_of(fen: &str) -> Option<Termination> {
+        termination(&new_history(fen))
+    }
+
+    #[test]
+    fn checkmate_wins() {
+        let mut history = new_history(notation::STARTING_FEN);
+
+        play(&mut history, &["f3", "e5", "g4", "Qh4"]);
+
+        let to_move = history.player_to_move_at(history.current());
+        let result = detect_result(&history, &to_move).unwrap();
+
+        assert_eq!(result.termination, Termination::Checkmate);
+        assert_eq!(result.pgn_result(history.board_at(0)), "0-1");
+    }
+
+    #[test]
+    fn stalemate_draws() {
+        assert_eq!(
+            termination_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(Termination::Stalemate)
+        );
+    }
+
+    #[test]
+    fn dead_positions_draw() {
+        let dead = [
+            "8/8/8/4k3/8/8/8/4K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/3NK3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/2B1K3 b - - 0 1",
+            // Both bishops on dark squares.
+            "5b2/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "8/8/8/4k3/8/4B3/8/2B1K3 w - - 0 1",
+        ];
+
+        for fen in dead {
+            assert_eq!(
+                termination_of(fen),
+                Some(Termination::InsufficientMaterial),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn positions_where_mate_is_possible_go_on() {
+        let playable = [
+            // Bishops on squares of different colors.
+            "2b5/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "6n1/8/8/4k3/8/8/8/1N2K3 w - - 0 1",
+            "6n1/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/1NN1K3 w - - 0 1",
+            "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1",
+        ];
+
+        for fen in playable {
+            assert_eq!(termination_of(fen), None, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn timeout_needs_mating_material_of_the_opponent() {
+        let (board, _) = notation::parse_fen("6n1/8/8/4k3/8/8/8/1N2K3 w - - 0 1").unwrap();
+
+        assert_eq!(
+            timeout_result(&board, &Player::You).termination,
+            Termination::TimeoutVsInsufficientMaterial
+        );
+
+        let (board, _) = notation::parse_fen("7r/8/8/4k3/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let player = rules::player_of_color(&board, &Color::White);
+        let result = timeout_result(&board, &player);
+
+        assert_eq!(result.termination, Termination::Timeout);
+        assert_eq!(result.pgn_result(&board), "0-1");
+    }
+
+    #[test]
+    fn threefold_repetition_draws() {
+        let mut history = new_history(notation::STARTING_FEN);
+
+        play(
+            &mut history,
+            &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"],
+        );
+        assert_eq!(termination(&history), None);
+
+        play(&mut history, &["Ng8"]);
+        assert_eq!(
+            termination(&history),
+            Some(Termination::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn fifty_moves_without_progress_draw() {
+        let mut history = new_history(notation::STARTING_FEN);
+
+        play(&mut history, &["e4", "e5"]);
+        assert_eq!(count_plies_since_progress(&history), 0);
+
+        for _ in 0..25 {
+            play(&mut history, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+
+        assert_eq!(count_plies_since_progress(&history), 100);
+        assert_eq!(termination(&history), Some(Termination::FiftyMoveRule));
+    }
+}
@@ -0,0 +1,279 @@
+use chess_logic::{Board, Color};
+
+use crate::{
+    history::{History, NodeId},
+    notation, Game,
+};
+
+/// Maximum length of a line of movetext, as recommended by the PGN standard.
+const MAX_LINE_LENGTH: usize = 79;
+/// Start of the FEN of the standard starting position. Games starting from
+/// it need no `FEN` tag.
+const STANDARD_PLACEMENT: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w";
+
+/// Exports the game as PGN, including the side variations in nested
+/// parentheses, the glyphs and comments of the moves and the annotations as
+/// `[%csl]`/`[%cal]` comments.
+pub fn format_pgn(game: &Game) -> String {
+    let history = &game.history;
+    let result = match &game.result {
+        Some(result) => result.pgn_result(&game.board),
+        None => "*",
+    };
+
+    let mut pgn = String::new();
+
+    for (tag, value) in [
+        ("Event", "?"),
+        ("Site", "?"),
+        ("Date", "????.??.??"),
+        ("Round", "?"),
+        ("White", "?"),
+        ("Black", "?"),
+        ("Result", result),
+    ] {
+        pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+    }
+
+    let start_fen = notation::format_fen(history.start(), history.start_player());
+
+    if !start_fen.starts_with(STANDARD_PLACEMENT) {
+        pgn.push_str("[SetUp \"1\"]\n");
+        pgn.push_str(&format!("[FEN \"{}\"]\n", start_fen));
+    }
+
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+
+    if let Some(comment) = get_comment(game, history.start()) {
+        tokens.push(comment);
+    }
+
+    if let Some(first) = history.children(None).first() {
+        push_line(game, *first, &mut tokens);
+    }
+
+    tokens.push(result.to_owned());
+
+    pgn.push_str(&wrap_tokens(&tokens));
+    pgn.push('\n');
+
+    pgn
+}
+
+/// Adds the tokens of the line starting at `first`, with the side variations
+/// branching off it.
+fn push_line(game: &Game, first: NodeId, tokens: &mut Vec<String>) {
+    let history = &game.history;
+    let mut id = first;
+    let mut show_number = true;
+
+    loop {
+        let ply = history.ply(id);
+        let is_white = matches!(
+            history.start().get_color_of_player(&ply.player),
+            Color::White
+        );
+
+        if is_white {
+            tokens.push(format!("{}.", get_move_number(history, id)));
+        } else if show_number {
+            tokens.push(format!("{}...", get_move_number(history, id)));
+        }
+
+        tokens.push(ply.san.clone());
+
+        if let Some(nag) = ply.nag {
+            tokens.push(format!("${}", nag));
+        }
+
+        // Comments end at the first closing brace, so it can't be part of one.
+        if let Some(comment) = &ply.comment {
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+        }
+
+        if let Some(comment) = get_comment(game, &ply.board) {
+            tokens.push(comment);
+        }
+
+        show_number = false;
+
+        let siblings = history.children(history.parent(id));
+
+        if siblings.first() == Some(&id) && siblings.len() > 1 {
+            for variation in &siblings[1..] {
+                tokens.push("(".to_owned());
+                push_line(game, *variation, tokens);
+                tokens.push(")".to_owned());
+            }
+
+            show_number = true;
+        }
+
+        match history.children(Some(id)).first() {
+            Some(next) => id = *next,
+            None => break,
+        }
+    }
+}
+
+/// Comment with the annotations of the position, if it has any.
+fn get_comment(game: &Game, board: &Board) -> Option<String> {
+    let annotations = game.annotations.get(board)?;
+
+    if annotations.is_empty() {
+        return None;
+    }
+
+    Some(format!("{{{}}}", annotations.to_pgn_commands(board)))
+}
+
+/// Symbol of a Numeric Annotation Glyph for move quality, like `"?!"` for
+/// `$6`. Other glyphs have no symbol.
+pub fn nag_symbol(nag: u8) -> Option<&'static str> {
+    match nag {
+        1 => Some("!"),
+        2 => Some("?"),
+        3 => Some("!!"),
+        4 => Some("??"),
+        5 => Some("!?"),
+        6 => Some("?!"),
+        _ => None,
+    }
+}
+
+/// Full move number of the node, counting from the starting position.
+pub fn get_move_number(history: &History, id: NodeId) -> usize {
+    let depth = history.depth(id);
+    let starts_with_white = matches!(
+        history.start().get_color_of_player(history.start_player()),
+        Color::White
+    );
+
+    match starts_with_white {
+        true => (depth - 1) / 2 + 1,
+        false => depth / 2 + 1,
+    }
+}
+
+/// Joins the tokens with spaces, without a space after an opening or before
+/// a closing parenthesis, and breaks lines before they get too long.
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    let mut previous: Option<&str> = None;
+
+    for token in tokens {
+        let needs_space = match previous {
+            Some("(") | None => false,
+            _ => token != ")",
+        };
+
+        if needs_space && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if needs_space {
+            text.push(' ');
+            line_length += 1;
+        }
+
+        text.push_str(token);
+        line_length += token.len();
+        previous = Some(token);
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules;
+
+    fn new_game(fen: &str) -> Game {
+        let (board, to_move) = notation::parse_fen(fen).unwrap();
+
+        Game::new_with_player(board, to_move)
+    }
+
+    /// Plays the moves, given in SAN, from the shown position.
+    fn play(game: &mut Game, sans: &[&str]) {
+        for san in sans {
+            let before = game.board.clone();
+            let mv = notation::parse_san(&before, &game.to_move, san).unwrap();
+
+            game.board = rules::apply_move(&before, &mv).unwrap();
+            game.record_move(&before, mv.from, mv.to);
+        }
+    }
+
+    /// Movetext of the PGN, without the tags.
+    fn movetext(game: &Game) -> String {
+        let pgn = format_pgn(game);
+        let (_, movetext) = pgn.split_once("\n\n").unwrap();
+
+        movetext.trim_end().to_owned()
+    }
+
+    #[test]
+    fn nested_variations() {
+        let mut game = new_game(notation::STARTING_FEN);
+
+        play(&mut game, &["e4", "e5", "Nf3"]);
+        game.go_to_ply(1);
+        play(&mut game, &["c5", "Nf3", "d6"]);
+        game.go_to_ply(3);
+        play(&mut game, &["Nc6"]);
+
+        assert_eq!(
+            movetext(&game),
+            "1. e4 e5 (1... c5 2. Nf3 d6 (2... Nc6)) 2. Nf3 *"
+        );
+    }
+
+    #[test]
+    fn glyphs_and_comments() {
+        let mut game = new_game(notation::STARTING_FEN);
+
+        play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+
+        let line = game.history.line().to_vec();
+        game.history
+            .annotate_move(line[2], Some(4), Some("Loses {at once}".to_owned()));
+
+        assert_eq!(
+            movetext(&game),
+            "1. f3 e5 2. g4 $4 {Loses {at once)} Qh4# 0-1"
+        );
+        assert_eq!(nag_symbol(4), Some("??"));
+        assert_eq!(nag_symbol(10), None);
+    }
+
+    #[test]
+    fn set_up_position() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1";
+        let mut game = new_game(fen);
+
+        play(&mut game, &["Kd7", "e4"]);
+
+        let pgn = format_pgn(&game);
+
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n"));
+        assert!(pgn.ends_with("\n1... Kd7 2. e4 *\n"));
+    }
+
+    #[test]
+    fn long_movetext_is_wrapped() {
+        let mut game = new_game(notation::STARTING_FEN);
+
+        for _ in 0..10 {
+            play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+
+        let text = movetext(&game);
+
+        assert!(text.lines().count() > 1);
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+}
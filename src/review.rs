@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use chess_logic::Color;
+
+use crate::{
+    engine::{self, BackgroundSearch, SearchLimits},
+    history::{History, NodeId},
+    notation,
+    rules::{self, Move},
+    Game,
+};
+
+/// Limits of the search of every position. Deep enough to see simple
+/// tactics, short enough to review a game in well under a minute.
+const REVIEW_DEPTH: u32 = 4;
+const REVIEW_TIME: Duration = Duration::from_millis(500);
+/// Scores are capped here before they are turned into winning chances, so
+/// mates count as completely won positions.
+const MAX_REVIEW_SCORE: i32 = 1_000;
+/// Drops of the winning chances, in percentage points, from which a move
+/// is flagged.
+const INACCURACY_DROP: f64 = 5.0;
+const MISTAKE_DROP: f64 = 10.0;
+const BLUNDER_DROP: f64 = 15.0;
+
+/// How good a move was compared to the best one the engine found.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveClass {
+    /// The move the engine would have played.
+    Best,
+    /// Another move that keeps the evaluation about the same.
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    /// Classifies a move by how much it lowered the winning chances of its
+    /// player, in percentage points.
+    fn from_drop(drop: f64, is_best: bool) -> Self {
+        match drop {
+            _ if is_best => MoveClass::Best,
+            drop if drop >= BLUNDER_DROP => MoveClass::Blunder,
+            drop if drop >= MISTAKE_DROP => MoveClass::Mistake,
+            drop if drop >= INACCURACY_DROP => MoveClass::Inaccuracy,
+            _ => MoveClass::Good,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoveClass::Best => "Best move",
+            MoveClass::Good => "Good move",
+            MoveClass::Inaccuracy => "Inaccuracy",
+            MoveClass::Mistake => "Mistake",
+            MoveClass::Blunder => "Blunder",
+        }
+    }
+
+    /// Numeric Annotation Glyph of the class, `None` for moves that are not
+    /// flagged.
+    pub fn nag(&self) -> Option<u8> {
+        match self {
+            MoveClass::Best | MoveClass::Good => None,
+            MoveClass::Inaccuracy => Some(6),
+            MoveClass::Mistake => Some(2),
+            MoveClass::Blunder => Some(4),
+        }
+    }
+}
+
+/// Review of a single move of the game.
+#[derive(Clone)]
+pub struct MoveReview {
+    /// How close the move came to the best one, from `0.0` to `100.0`.
+    pub accuracy: f64,
+    /// Move the engine would have played, in SAN.
+    pub best_san: Option<String>,
+    pub class: MoveClass,
+    /// Color of the player who made the move.
+    pub color: Color,
+    /// Evaluation after the move, in centipawns from white's point of view.
+    pub evaluation: i32,
+    pub id: NodeId,
+    pub san: String,
+}
+
+/// Review of the moves of a line of the game.
+#[derive(Clone, Default)]
+pub struct GameReport {
+    pub moves: Vec<MoveReview>,
+}
+
+impl GameReport {
+    /// Average accuracy of the moves of the player, `None` if they made
+    /// none.
+    pub fn accuracy(&self, color: &Color) -> Option<f64> {
+        let accuracies: Vec<f64> = self.moves_of(color).map(|review| review.accuracy).collect();
+
+        match accuracies.len() {
+            0 => None,
+            count => Some(accuracies.iter().sum::<f64>() / count as f64),
+        }
+    }
+
+    /// Number of moves of the player in the class.
+    pub fn count(&self, color: &Color, class: MoveClass) -> usize {
+        self.moves_of(color)
+            .filter(|review| review.class == class)
+            .count()
+    }
+
+    fn moves_of<'a>(&'a self, color: &'a Color) -> impl Iterator<Item = &'a MoveReview> {
+        self.moves
+            .iter()
+            .filter(move |review| rules::is_same_color(&review.color, color))
+    }
+
+    /// Whether the reviewed moves are still in the history, which is not the
+    /// case once they were deleted or another game was loaded.
+    pub fn matches(&self, history: &History) -> bool {
+        self.moves
+            .iter()
+            .all(|review| history.contains(review.id) && history.ply(review.id).san == review.san)
+    }
+
+    /// Adds the glyph of the class and a comment with the evaluation and the
+    /// best move to every flagged move. The comment is appended to the one
+    /// of the user, the other moves are left alone.
+    pub fn annotate(&self, game: &mut Game) {
+        for review in &self.moves {
+            let nag = match review.class.nag() {
+                Some(nag) => nag,
+                None => continue,
+            };
+            let comment = match &review.best_san {
+                Some(best_san) => format!(
+                    "{} ({}). {} was best.",
+                    review.class.name(),
+                    engine::format_score(review.evaluation),
+                    best_san
+                ),
+                None => format!(
+                    "{} ({}).",
+                    review.class.name(),
+                    engine::format_score(review.evaluation)
+                ),
+            };
+
+            // Applying the report again must not repeat the comment.
+            let comment = match &game.history.ply(review.id).comment {
+                Some(old) if old.contains(&comment) => old.clone(),
+                Some(old) => format!("{} {}", old, comment),
+                None => comment,
+            };
+
+            game.history
+                .annotate_move(review.id, Some(nag), Some(comment));
+        }
+    }
+}
+
+/// Evaluation of a position of the reviewed line.
+struct Evaluation {
+    best_move: Option<Move>,
+    /// In centipawns from the point of view of the player to move.
+    score: i32,
+}
+
+/// Searches every position of a line of the game one after another, so the
+/// moves can be reviewed once all are done.
+pub struct GameReview {
+    evaluations: Vec<Evaluation>,
+    history: History,
+    /// Nodes of the reviewed line, in the order they were played.
+    line: Vec<NodeId>,
+    search: Option<BackgroundSearch>,
+}
+
+impl GameReview {
+    /// Starts reviewing the line of the history, from the starting position
+    /// to its last move.
+    pub fn start(history: &History) -> Self {
+        Self {
+            evaluations: Vec::new(),
+            history: history.clone(),
+            line: history.line().to_vec(),
+            search: None,
+        }
+    }
+
+    /// Searched positions and all positions of the line, the starting
+    /// position included.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.evaluations.len(), self.line.len() + 1)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.evaluations.len() > self.line.len()
+    }
+
+    /// Continues with the search of the next position. Has to be called
+    /// regularly, e.g. every frame, until the review is finished.
+    pub fn poll(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        let index = self.evaluations.len();
+        let board = self.history.board_at(index);
+        let player = self.history.player_to_move_at(index);
+
+        let search = self.search.get_or_insert_with(|| {
+            let limits = SearchLimits {
+                depth: REVIEW_DEPTH,
+                multi_pv: 1,
+                randomness: 0,
+                time: Some(REVIEW_TIME),
+            };
+
+            BackgroundSearch::start(board.clone(), player.clone(), limits)
+        });
+
+        search.poll();
+
+        if !search.is_finished() {
+            return;
+        }
+
+        let evaluation = match search.latest() {
+            Some(info) => Evaluation {
+                best_move: info.best_move().copied(),
+                score: info.score,
+            },
+            // Without a legal move the game is over.
+            None if rules::is_in_check(board, &player) => Evaluation {
+                best_move: None,
+                score: -engine::MATE_SCORE,
+            },
+            None => Evaluation {
+                best_move: None,
+                score: 0,
+            },
+        };
+
+        self.evaluations.push(evaluation);
+        self.search = None;
+    }
+
+    /// Reviews the moves once every position was searched.
+    pub fn report(&self) -> Option<GameReport> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        let moves = self
+            .line
+            .iter()
+            .enumerate()
+            .map(|(index, id)| self.review_move(index, *id))
+            .collect();
+
+        Some(GameReport { moves })
+    }
+
+    /// Compares the move leading from the position at `index` to the next
+    /// one with the best move of the position.
+    fn review_move(&self, index: usize, id: NodeId) -> MoveReview {
+        let before = &self.evaluations[index];
+        let after = &self.evaluations[index + 1];
+        let board = self.history.board_at(index);
+        let ply = self.history.ply(id);
+
+        // The search of the next position sees one move deeper, so the
+        // played move can turn out better than the best one, or the best
+        // move worse than expected.
+        let played_score = -after.score;
+        let is_best = before.best_move == Some(ply.mv);
+        let drop = match is_best {
+            true => 0.0,
+            false => winning_chances(before.score) - winning_chances(played_score),
+        };
+        let color = board.get_color_of_player(&ply.player).clone();
+        let evaluation = match color {
+            Color::White => played_score,
+            Color::Black => -played_score,
+        };
+
+        MoveReview {
+            accuracy: move_accuracy(drop),
+            best_san: before
+                .best_move
+                .map(|mv| notation::format_san(board, &ply.player, &mv)),
+            class: MoveClass::from_drop(drop, is_best),
+            color,
+            evaluation,
+            id,
+            san: ply.san.clone(),
+        }
+    }
+}
+
+/// Chances of the player to win, in percent, for a score from their point
+/// of view. Uses the same curve as the Lichess analysis.
+fn winning_chances(score: i32) -> f64 {
+    let score = score.clamp(-MAX_REVIEW_SCORE, MAX_REVIEW_SCORE) as f64;
+
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * score).exp()) - 1.0)
+}
+
+/// Accuracy of a move that lowered the winning chances of its player by
+/// `drop` percentage points, `100.0` for a move that kept them.
+fn move_accuracy(drop: f64) -> f64 {
+    (103.1668 * (-0.04354 * drop.max(0.0)).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(game: &Game, index: usize, class: MoveClass) -> MoveReview {
+        let id = game.history.line()[index];
+
+        MoveReview {
+            accuracy: 50.0,
+            best_san: Some("d4".to_owned()),
+            class,
+            color: Color::White,
+            evaluation: -150,
+            id,
+            san: game.history.ply(id).san.clone(),
+        }
+    }
+
+    #[test]
+    fn annotate_keeps_the_comments_of_the_user() {
+        let (board, to_move) = notation::parse_fen(notation::STARTING_FEN).unwrap();
+        let mut game = Game::new_with_player(board, to_move);
+
+        for san in ["e4", "e5", "Nf3"] {
+            let before = game.board.clone();
+            let mv = notation::parse_san(&before, &game.to_move, san).unwrap();
+
+            game.board = rules::apply_move(&before, &mv).unwrap();
+            game.record_move(&before, mv.from, mv.to);
+        }
+
+        let line = game.history.line().to_vec();
+
+        game.history
+            .annotate_move(line[0], Some(1), Some("The main line.".to_owned()));
+        game.history
+            .annotate_move(line[2], None, Some("Develops.".to_owned()));
+
+        let report = GameReport {
+            moves: vec![
+                review(&game, 0, MoveClass::Good),
+                review(&game, 2, MoveClass::Mistake),
+            ],
+        };
+
+        report.annotate(&mut game);
+        report.annotate(&mut game);
+
+        let first = game.history.ply(line[0]);
+        let third = game.history.ply(line[2]);
+
+        assert_eq!(first.nag, Some(1));
+        assert_eq!(first.comment.as_deref(), Some("The main line."));
+        assert_eq!(third.nag, Some(2));
+        assert_eq!(
+            third.comment.as_deref(),
+            Some("Develops. Mistake (-1.50). d4 was best.")
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use std::mem;
+
+use chess_logic::{info_board::PosInfo, Board, Color, Piece, Player};
+
+/// A square on the board as `(x, y)`, with `(0, 0)` being the top left.
+pub type Pos = (i8, i8);
+
+/// Returns every square the piece at the given position could move to or
+/// capture on, as reported by [`Board::get_moves_of_selected`].
+pub fn moves_of_piece_at(board: &Board, x: i8, y: i8) -> Vec<Pos> {
+    if board.get(x, y).is_none() {
+        return Vec::new();
+    }
+
+    let mut board = board.clone();
+    board.update_selected(x, y);
+
+    let info_board = board.get_moves_of_selected();
+    let mut moves = Vec::new();
+
+    for move_y in 0..board.height() {
+        for move_x in 0..board.width() {
+            match info_board.get(move_x, move_y) {
+                PosInfo::Move | PosInfo::PieceHit(_) => moves.push((move_x, move_y)),
+                PosInfo::None | PosInfo::Piece(_) => (),
+            }
+        }
+    }
+
+    moves
+}
+
+pub fn find_king(board: &Board, player: &Player) -> Option<Pos> {
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if let Some(ins) = board.get(x, y) {
+                if matches!(ins.piece, Piece::King) && &ins.player == player {
+                    return Some((x, y));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Checks if any piece of `attacker` could capture on the given square.
+pub fn is_attacked_by(board: &Board, (x, y): Pos, attacker: &Player) -> bool {
+    for piece_y in 0..board.height() {
+        for piece_x in 0..board.width() {
+            let is_attacker = match board.get(piece_x, piece_y) {
+                Some(ins) => &ins.player == attacker,
+                None => false,
+            };
+
+            if is_attacker && moves_of_piece_at(board, piece_x, piece_y).contains(&(x, y)) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+pub fn is_in_check(board: &Board, player: &Player) -> bool {
+    match find_king(board, player) {
+        Some(king_pos) => is_attacked_by(board, king_pos, &opponent_of(player)),
+        None => false,
+    }
+}
+
+/// Returns the position of the king that is currently in check, if any.
+pub fn get_checked_king_pos(board: &Board) -> Option<Pos> {
+    [Player::You, Player::Opponent]
+        .iter()
+        .filter(|player| is_in_check(board, player))
+        .find_map(|player| find_king(board, player))
+}
+
+pub fn opponent_of(player: &Player) -> Player {
+    match player {
+        Player::You => Player::Opponent,
+        Player::Opponent => Player::You,
+    }
+}
+
+/// Player who plays the pieces of the color.
+pub fn player_of_color(board: &Board, color: &Color) -> Player {
+    match is_same_color(board.get_color_of_player(&Player::You), color) {
+        true => Player::You,
+        false => Player::Opponent,
+    }
+}
+
+pub fn is_same_piece(a: &Piece, b: &Piece) -> bool {
+    mem::discriminant(a) == mem::discriminant(b)
+}
+
+pub fn is_same_color(a: &Color, b: &Color) -> bool {
+    mem::discriminant(a) == mem::discriminant(b)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Move {
+    pub from: Pos,
+    pub promotion: Option<Piece>,
+    pub to: Pos,
+}
+
+impl Move {
+    pub fn new(from: Pos, to: Pos) -> Self {
+        Self {
+            from,
+            promotion: None,
+            to,
+        }
+    }
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        let is_same_promotion = match (&self.promotion, &other.promotion) {
+            (None, None) => true,
+            (Some(a), Some(b)) => is_same_piece(a, b),
+            _ => false,
+        };
+
+        self.from == other.from && self.to == other.to && is_same_promotion
+    }
+}
+
+/// Plays the move on a copy of the board. Returns `None` if the board did not
+/// accept the move.
+///
+/// If the move reaches the last rank and no promotion is given, the pawn is
+/// promoted to a queen.
+pub fn apply_move(board: &Board, mv: &Move) -> Option<Board> {
+    let mut board = board.clone();
+
+    board.update_selected(mv.from.0, mv.from.1);
+
+    if !board.move_selected_to(mv.to.0, mv.to.1) {
+        return None;
+    }
+
+    if board.get_promote_pos().is_some() {
+        board.promote_piece_to(mv.promotion.unwrap_or(Piece::Queen));
+    }
+
+    Some(board)
+}
+
+/// Returns every move of `player` that does not leave their own king in
+/// check. Pawn moves to the last rank are returned once per promotion piece.
+pub fn legal_moves(board: &Board, player: &Player) -> Vec<Move> {
+    let mut moves = Vec::new();
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let ins = match board.get(x, y) {
+                Some(ins) if &ins.player == player => ins,
+                _ => continue,
+            };
+
+            for to in moves_of_piece_at(board, x, y) {
+                let is_promotion =
+                    matches!(ins.piece, Piece::Pawn) && (to.1 == 0 || to.1 == board.height() - 1);
+
+                let candidates = if is_promotion {
+                    PROMOTION_PIECES
+                        .iter()
+                        .map(|piece| Move {
+                            from: (x, y),
+                            promotion: Some(*piece),
+                            to,
+                        })
+                        .collect()
+                } else {
+                    vec![Move::new((x, y), to)]
+                };
+
+                for mv in candidates {
+                    if let Some(after) = apply_move(board, &mv) {
+                        if !is_in_check(&after, player) {
+                            moves.push(mv);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    moves
+}
+
+/// Pieces a pawn can be promoted to, in the order they are usually offered.
+pub const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+/// The player playing white moves first.
+pub fn get_starting_player(board: &Board) -> Player {
+    match board.get_color_of_player(&Player::You) {
+        Color::White => Player::You,
+        Color::Black => Player::Opponent,
+    }
+}
@@ -1,11 +1,48 @@
-use chess_logic::{board::PieceInstance, Board};
+use chess_logic::{board::PieceInstance, Board, Player};
 use linked_hash_map::LinkedHashMap;
 use yaml_rust::Yaml;
 
-pub fn serialize_game(board: &Board) -> Yaml {
+use crate::{
+    annotations::AnnotationStore,
+    history::{History, NodeId},
+    notation,
+    outcome::GameResult,
+    Game,
+};
+
+pub fn serialize_game(game: &Game) -> Yaml {
     let mut ser_game = LinkedHashMap::new();
 
-    ser_game.insert(Yaml::String("board".to_owned()), serialize_board(board));
+    ser_game.insert(
+        Yaml::String("board".to_owned()),
+        serialize_board(&game.board),
+    );
+    ser_game.insert(
+        Yaml::String("your_color".to_owned()),
+        Yaml::String(format!(
+            "{:?}",
+            game.board.get_color_of_player(&Player::You)
+        )),
+    );
+    ser_game.insert(
+        Yaml::String("to_move".to_owned()),
+        Yaml::String(format!("{:?}", game.to_move)),
+    );
+    ser_game.insert(
+        Yaml::String("history".to_owned()),
+        serialize_history(&game.history),
+    );
+    ser_game.insert(
+        Yaml::String("result".to_owned()),
+        match &game.result {
+            Some(result) => serialize_result(result, &game.board),
+            None => Yaml::Null,
+        },
+    );
+    ser_game.insert(
+        Yaml::String("annotations".to_owned()),
+        serialize_annotations(&game.annotations, &game.board),
+    );
 
     Yaml::Hash(ser_game)
 }
@@ -53,3 +90,143 @@ fn serialize_piece_ins(ins: &PieceInstance) -> Yaml {
 
     Yaml::Hash(ser)
 }
+
+/// Annotations are stored as PGN comment commands (`[%csl ...][%cal ...]`)
+/// keyed by the position they belong to.
+fn serialize_annotations(annotations: &AnnotationStore, board: &Board) -> Yaml {
+    let mut ser = LinkedHashMap::new();
+
+    for (position_key, position_annotations) in annotations.iter() {
+        ser.insert(
+            Yaml::String(position_key.clone()),
+            Yaml::String(position_annotations.to_pgn_commands(board)),
+        );
+    }
+
+    Yaml::Hash(ser)
+}
+
+/// The history is stored as the starting position and the moves in UCI
+/// notation. The positions in between are recreated when loading.
+///
+/// A move with alternatives is stored as a hash of the move and its
+/// `variations`, each of which is a list of moves in the same format. The
+/// same hash holds the `nag` and the `comment` of an annotated move. The
+/// current position is stored as the index of the chosen move at every
+/// ply, starting from the starting position.
+fn serialize_history(history: &History) -> Yaml {
+    let mut ser = LinkedHashMap::new();
+
+    let ser_moves = match history.children(None).first() {
+        Some(first) => serialize_line(history, *first),
+        None => Yaml::Array(Vec::new()),
+    };
+
+    ser.insert(
+        Yaml::String("start".to_owned()),
+        serialize_board(history.start()),
+    );
+    ser.insert(
+        Yaml::String("start_to_move".to_owned()),
+        Yaml::String(format!("{:?}", history.start_player())),
+    );
+    ser.insert(Yaml::String("moves".to_owned()), ser_moves);
+    ser.insert(
+        Yaml::String("current".to_owned()),
+        serialize_current(history),
+    );
+
+    Yaml::Hash(ser)
+}
+
+fn serialize_line(history: &History, first: NodeId) -> Yaml {
+    let mut ser_line = Vec::new();
+    let mut node = Some(first);
+
+    while let Some(id) = node {
+        let ply = history.ply(id);
+        let ser_move = Yaml::String(notation::format_uci(history.board_before(id), &ply.mv));
+        let siblings = history.children(history.parent(id));
+        let has_variations = siblings.first() == Some(&id) && siblings.len() > 1;
+
+        if has_variations || ply.nag.is_some() || ply.comment.is_some() {
+            let mut ser = LinkedHashMap::new();
+
+            ser.insert(Yaml::String("move".to_owned()), ser_move);
+
+            if has_variations {
+                let ser_variations = siblings[1..]
+                    .iter()
+                    .map(|variation| serialize_line(history, *variation))
+                    .collect();
+
+                ser.insert(
+                    Yaml::String("variations".to_owned()),
+                    Yaml::Array(ser_variations),
+                );
+            }
+
+            if let Some(nag) = ply.nag {
+                ser.insert(Yaml::String("nag".to_owned()), Yaml::Integer(nag as i64));
+            }
+
+            if let Some(comment) = &ply.comment {
+                ser.insert(
+                    Yaml::String("comment".to_owned()),
+                    Yaml::String(comment.clone()),
+                );
+            }
+
+            ser_line.push(Yaml::Hash(ser));
+        } else {
+            ser_line.push(ser_move);
+        }
+
+        node = history.children(Some(id)).first().copied();
+    }
+
+    Yaml::Array(ser_line)
+}
+
+fn serialize_current(history: &History) -> Yaml {
+    let mut path = Vec::new();
+    let mut node = history.current_node();
+
+    while let Some(id) = node {
+        let parent = history.parent(id);
+        let index = history
+            .children(parent)
+            .iter()
+            .position(|child| *child == id)
+            .unwrap();
+
+        path.push(Yaml::Integer(index as i64));
+        node = parent;
+    }
+
+    path.reverse();
+
+    Yaml::Array(path)
+}
+
+fn serialize_result(result: &GameResult, board: &Board) -> Yaml {
+    let mut ser = LinkedHashMap::new();
+
+    ser.insert(
+        Yaml::String("result".to_owned()),
+        Yaml::String(result.pgn_result(board).to_owned()),
+    );
+    ser.insert(
+        Yaml::String("termination".to_owned()),
+        Yaml::String(format!("{:?}", result.termination)),
+    );
+    ser.insert(
+        Yaml::String("winner".to_owned()),
+        match &result.winner {
+            Some(winner) => Yaml::String(format!("{:?}", winner)),
+            None => Yaml::Null,
+        },
+    );
+
+    Yaml::Hash(ser)
+}
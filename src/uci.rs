@@ -0,0 +1,312 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use crate::{engine, engine_process::EngineProcess, notation, Game};
+
+/// How long the engine may take to answer `uci` and `isready`.
+pub(crate) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Score of an `info` line, from the point of view of the player to move.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciScore {
+    Centipawns(i32),
+    /// Moves until mate, negative if the player to move gets mated.
+    Mate(i32),
+}
+
+impl UciScore {
+    /// Converts the score to the scale of [`engine::search`], so both can be
+    /// shown the same way.
+    pub fn to_engine_score(&self) -> i32 {
+        match *self {
+            UciScore::Centipawns(centipawns) => centipawns,
+            UciScore::Mate(moves) if moves > 0 => engine::MATE_SCORE - (2 * moves - 1),
+            UciScore::Mate(moves) => -engine::MATE_SCORE - 2 * moves,
+        }
+    }
+}
+
+/// The fields of an `info` line the client uses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Moves in UCI notation.
+    pub pv: Vec<String>,
+    pub score: Option<UciScore>,
+}
+
+/// Option the engine announced during the handshake.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    /// `check`, `spin`, `combo`, `button` or `string`.
+    pub kind: String,
+    pub default: Option<String>,
+}
+
+/// Output of the engine while searching.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciEvent {
+    Info(UciInfo),
+    /// End of a search. The move is `None` if the engine had none to play.
+    BestMove {
+        mv: Option<String>,
+        ponder: Option<String>,
+    },
+}
+
+/// When the engine has to stop searching.
+#[derive(Clone, Debug)]
+pub enum GoLimits {
+    /// Until [`UciEngine::stop`] is called.
+    Infinite,
+    Depth(u32),
+    MoveTime(Duration),
+}
+
+/// External engine speaking UCI.
+pub struct UciEngine {
+    author: String,
+    name: String,
+    options: Vec<UciOption>,
+    process: EngineProcess,
+    searching: bool,
+}
+
+impl UciEngine {
+    /// Starts the engine and waits until it finished the handshake.
+    pub fn start(program: &str) -> Result<Self, Box<dyn Error>> {
+        let mut engine = Self {
+            author: String::new(),
+            name: program.to_owned(),
+            options: Vec::new(),
+            process: EngineProcess::start(program)?,
+            searching: false,
+        };
+
+        engine.process.send("uci")?;
+
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+
+        loop {
+            let line = engine.process.read_line(deadline)?;
+
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_owned();
+            } else if let Some(author) = line.strip_prefix("id author ") {
+                engine.author = author.trim().to_owned();
+            } else if let Some(option) = parse_option(&line) {
+                engine.options.push(option);
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        engine.wait_ready()?;
+
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    /// Whether the engine closed its output, usually because it crashed.
+    pub fn has_exited(&self) -> bool {
+        self.process.has_exited()
+    }
+
+    /// Sends the option without waiting for the engine to apply it. Engines
+    /// handle their input in order, so it is in effect for the next search.
+    /// Use [`Self::wait_ready`] to wait for it.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        self.process
+            .send(&format!("setoption name {} value {}", name, value))
+    }
+
+    /// Tells the engine the next position belongs to another game.
+    pub fn new_game(&mut self) -> Result<(), Box<dyn Error>> {
+        self.process.send("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    /// Sends `isready` and waits for `readyok`. Must not be called while
+    /// searching, as the output of the search would be dropped.
+    pub fn wait_ready(&mut self) -> Result<(), Box<dyn Error>> {
+        self.process.send("isready")?;
+
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+
+        while self.process.read_line(deadline)?.trim() != "readyok" {}
+
+        Ok(())
+    }
+
+    /// Sets the position as the starting position with the moves, in UCI
+    /// notation, played from it.
+    pub fn set_position(&mut self, fen: &str, moves: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut command = format!("position fen {}", fen);
+
+        if !moves.is_empty() {
+            command.push_str(" moves ");
+            command.push_str(&moves.join(" "));
+        }
+
+        self.process.send(&command)
+    }
+
+    pub fn go(&mut self, limits: &GoLimits) -> Result<(), Box<dyn Error>> {
+        let command = match limits {
+            GoLimits::Infinite => "go infinite".to_owned(),
+            GoLimits::Depth(depth) => format!("go depth {}", depth),
+            GoLimits::MoveTime(time) => format!("go movetime {}", time.as_millis()),
+        };
+
+        self.process.send(&command)?;
+        self.searching = true;
+
+        Ok(())
+    }
+
+    /// Asks the engine to finish the search. It still answers with a
+    /// [`UciEvent::BestMove`].
+    pub fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.searching {
+            true => self.process.send("stop"),
+            false => Ok(()),
+        }
+    }
+
+    /// Takes the output of the engine without waiting for more. Has to be
+    /// called regularly while searching, e.g. every frame.
+    pub fn poll(&mut self) -> Vec<UciEvent> {
+        let mut events = Vec::new();
+
+        while let Some(line) = self.process.try_read_line() {
+            if let Some(info) = parse_info(&line) {
+                events.push(UciEvent::Info(info));
+            } else if let Some(event) = parse_bestmove(&line) {
+                self.searching = false;
+                events.push(event);
+            }
+        }
+
+        if self.process.has_exited() {
+            self.searching = false;
+        }
+
+        events
+    }
+}
+
+impl Drop for UciEngine {
+    /// The process quits when it is dropped, the search has to be stopped
+    /// before.
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Starting position of the game and the moves of its line up to the shown
+/// position, in UCI notation, as expected by [`UciEngine::set_position`]
+/// and [`crate::cecp::CecpEngine::set_position`].
+/// Sending the moves instead of only the position lets the engine detect
+/// repetitions.
+pub fn game_position(game: &Game) -> (String, Vec<String>) {
+    let history = &game.history;
+    let fen = notation::format_fen(history.start(), history.start_player());
+    let moves = history.line()[..history.current()]
+        .iter()
+        .map(|id| notation::format_uci(history.board_before(*id), &history.ply(*id).mv))
+        .collect();
+
+    (fen, moves)
+}
+
+/// Parses a line like `info depth 12 score cp 31 nodes 48210 pv e2e4 e7e5`.
+/// Fields the client does not use are skipped.
+pub fn parse_info(line: &str) -> Option<UciInfo> {
+    let mut words = line.split_whitespace();
+
+    if words.next()? != "info" {
+        return None;
+    }
+
+    let mut info = UciInfo::default();
+
+    while let Some(word) = words.next() {
+        match word {
+            "depth" => info.depth = words.next()?.parse().ok(),
+            "multipv" => info.multipv = words.next()?.parse().ok(),
+            "nodes" => info.nodes = words.next()?.parse().ok(),
+            "score" => {
+                info.score = match (words.next()?, words.next()?.parse().ok()?) {
+                    ("cp", centipawns) => Some(UciScore::Centipawns(centipawns)),
+                    ("mate", moves) => Some(UciScore::Mate(moves)),
+                    _ => None,
+                }
+            }
+            "pv" => info.pv = words.by_ref().map(str::to_owned).collect(),
+            // The rest of the line is free text.
+            "string" => break,
+            _ => (),
+        }
+    }
+
+    Some(info)
+}
+
+/// Parses a line like `bestmove e2e4 ponder e7e5`.
+pub fn parse_bestmove(line: &str) -> Option<UciEvent> {
+    let mut words = line.split_whitespace();
+
+    if words.next()? != "bestmove" {
+        return None;
+    }
+
+    let mv = words
+        .next()
+        .filter(|mv| *mv != "(none)" && *mv != "0000")
+        .map(str::to_owned);
+    let ponder = match words.next() {
+        Some("ponder") => words.next().map(str::to_owned),
+        _ => None,
+    };
+
+    Some(UciEvent::BestMove { mv, ponder })
+}
+
+/// Parses a line like `option name Hash type spin default 16 min 1 max 1024`.
+/// Names may contain spaces.
+pub fn parse_option(line: &str) -> Option<UciOption> {
+    let rest = line.strip_prefix("option name ")?;
+    let (name, rest) = rest.split_once(" type ")?;
+    let mut words = rest.split_whitespace();
+    let kind = words.next()?.to_owned();
+    let default = match words.next() {
+        Some("default") => words.next().map(str::to_owned),
+        _ => None,
+    };
+
+    Some(UciOption {
+        name: name.trim().to_owned(),
+        kind,
+        default,
+    })
+}
@@ -0,0 +1,120 @@
+//! Runs the CECP client against a scripted mock engine, so no real engine has
+//! to be installed.
+#![cfg(unix)]
+
+mod common;
+
+use std::time::Duration;
+
+use chess::{
+    cecp::{self, CecpEngine},
+    uci::{GoLimits, UciEvent, UciInfo, UciScore},
+};
+
+use common::{wait_for_bestmove, MockEngine};
+
+/// Announces its features, moves `e7e5` whenever it has to move and prints
+/// one line of thinking output when analysing.
+const MOCK_ENGINE: &str = r#"#!/bin/sh
+while read -r line; do
+    case "$line" in
+        "protover 2")
+            echo 'feature myname="Mock Engine 1.0" setboard=1 usermove=1 san=1'
+            echo "feature done=1"
+            ;;
+        go)
+            echo "4 -25 12 1500 1. ... e5 2. Nf3"
+            echo "move e7e5"
+            ;;
+        analyze)
+            echo "6 31 80 9000 Nf3 Nc6"
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done
+"#;
+
+#[test]
+fn handshake_reads_features() {
+    let script = MockEngine::new("cecp_handshake", MOCK_ENGINE);
+    let engine = CecpEngine::start(script.path()).unwrap();
+
+    assert_eq!(engine.name(), "Mock Engine 1.0");
+}
+
+#[test]
+fn go_reports_thinking_and_move() {
+    let script = MockEngine::new("cecp_go", MOCK_ENGINE);
+    let mut engine = CecpEngine::start(script.path()).unwrap();
+
+    engine
+        .set_position(chess::notation::STARTING_FEN, &["e2e4".to_owned()])
+        .unwrap();
+    engine
+        .go(&GoLimits::MoveTime(Duration::from_millis(500)))
+        .unwrap();
+
+    assert!(engine.is_searching());
+    assert_eq!(
+        wait_for_bestmove(|| engine.poll()),
+        vec![
+            UciEvent::Info(UciInfo {
+                depth: Some(4),
+                multipv: None,
+                nodes: Some(1500),
+                pv: vec!["e5".to_owned(), "Nf3".to_owned()],
+                score: Some(UciScore::Centipawns(-25)),
+            }),
+            UciEvent::BestMove {
+                mv: Some("e7e5".to_owned()),
+                ponder: None,
+            },
+        ]
+    );
+    assert!(!engine.is_searching());
+}
+
+#[test]
+fn exit_ends_analysis_without_move() {
+    let script = MockEngine::new("cecp_analyze", MOCK_ENGINE);
+    let mut engine = CecpEngine::start(script.path()).unwrap();
+
+    engine.go(&GoLimits::Infinite).unwrap();
+    engine.stop().unwrap();
+
+    let events = wait_for_bestmove(|| engine.poll());
+
+    assert_eq!(
+        events.first(),
+        Some(&UciEvent::BestMove {
+            mv: None,
+            ponder: None,
+        })
+    );
+}
+
+#[test]
+fn parses_features_with_quoted_values() {
+    assert_eq!(
+        cecp::parse_features(r#"myname="Old Engine 2" setboard=1 done=0"#),
+        vec![
+            ("myname".to_owned(), "Old Engine 2".to_owned()),
+            ("setboard".to_owned(), "1".to_owned()),
+            ("done".to_owned(), "0".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn parses_mate_scores_of_both_conventions() {
+    let score = |line: &str| cecp::parse_thinking(line).unwrap().score.unwrap();
+
+    assert_eq!(score("9 156 1084 48000 Nf3"), UciScore::Centipawns(156));
+    assert_eq!(score("9 -156 1084 48000 Nf3"), UciScore::Centipawns(-156));
+    assert_eq!(score("9 100003 1084 48000 Qh5"), UciScore::Mate(3));
+    assert_eq!(score("9 -100002 1084 48000 Qh5"), UciScore::Mate(-2));
+    assert_eq!(score("9 31995 1084 48000 Qh5"), UciScore::Mate(3));
+    assert_eq!(score("9 -31996 1084 48000 Qh5"), UciScore::Mate(-2));
+}
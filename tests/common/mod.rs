@@ -0,0 +1,60 @@
+//! Scripted mock engines shared by the engine protocol tests.
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process,
+    time::{Duration, Instant},
+};
+
+use chess::uci::UciEvent;
+
+/// Shell script acting as an engine, removed again when dropped.
+pub struct MockEngine {
+    path: PathBuf,
+}
+
+impl MockEngine {
+    /// Writes the script to a file of its own, so tests can run in parallel.
+    pub fn new(name: &str, script: &str) -> Self {
+        let path = env::temp_dir().join(format!("mock_{}_{}.sh", process::id(), name));
+
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        Self { path }
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for MockEngine {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Polls the engine until the search ended, returning every event.
+pub fn wait_for_bestmove(mut poll: impl FnMut() -> Vec<UciEvent>) -> Vec<UciEvent> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+
+    while Instant::now() < deadline {
+        events.extend(poll());
+
+        let has_ended = events
+            .iter()
+            .any(|event| matches!(event, UciEvent::BestMove { .. }));
+
+        if has_ended {
+            return events;
+        }
+
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("no bestmove, got {:?}", events);
+}
@@ -0,0 +1,186 @@
+//! Runs the UCI client against a scripted mock engine, so no real engine has
+//! to be installed.
+#![cfg(unix)]
+
+mod common;
+
+use chess::uci::{self, GoLimits, UciEngine, UciEvent, UciInfo, UciScore};
+
+use common::{wait_for_bestmove, MockEngine};
+
+/// Answers the handshake and every search with the same two iterations.
+const MOCK_ENGINE: &str = r#"#!/bin/sh
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name Mock Engine"
+            echo "id author Tests"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name Skill Level type spin default 20 min 0 max 20"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        "go infinite")
+            echo "info depth 1 score cp 20 nodes 10 pv e2e4"
+            ;;
+        go*)
+            echo "info depth 1 score cp 20 nodes 10 pv e2e4"
+            echo "info depth 2 score mate 3 nodes 42 pv e2e4 e7e5"
+            echo "bestmove e2e4 ponder e7e5"
+            ;;
+        stop)
+            echo "bestmove d2d4"
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done
+"#;
+
+#[test]
+fn handshake_reads_id_and_options() {
+    let script = MockEngine::new("uci_handshake", MOCK_ENGINE);
+    let engine = UciEngine::start(script.path()).unwrap();
+
+    assert_eq!(engine.name(), "Mock Engine");
+    assert_eq!(engine.author(), "Tests");
+    assert_eq!(engine.options().len(), 2);
+    assert_eq!(engine.options()[1].name, "Skill Level");
+    assert_eq!(engine.options()[1].kind, "spin");
+    assert_eq!(engine.options()[1].default.as_deref(), Some("20"));
+}
+
+#[test]
+fn search_reports_info_and_bestmove() {
+    let script = MockEngine::new("uci_search", MOCK_ENGINE);
+    let mut engine = UciEngine::start(script.path()).unwrap();
+
+    engine.set_option("Hash", "32").unwrap();
+    engine.new_game().unwrap();
+    engine
+        .set_position(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[],
+        )
+        .unwrap();
+    engine.go(&GoLimits::Depth(2)).unwrap();
+
+    assert!(engine.is_searching());
+
+    let events = wait_for_bestmove(|| engine.poll());
+
+    assert!(!engine.is_searching());
+    assert_eq!(
+        events,
+        vec![
+            UciEvent::Info(UciInfo {
+                depth: Some(1),
+                multipv: None,
+                nodes: Some(10),
+                pv: vec!["e2e4".to_owned()],
+                score: Some(UciScore::Centipawns(20)),
+            }),
+            UciEvent::Info(UciInfo {
+                depth: Some(2),
+                multipv: None,
+                nodes: Some(42),
+                pv: vec!["e2e4".to_owned(), "e7e5".to_owned()],
+                score: Some(UciScore::Mate(3)),
+            }),
+            UciEvent::BestMove {
+                mv: Some("e2e4".to_owned()),
+                ponder: Some("e7e5".to_owned()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn stop_ends_infinite_search() {
+    let script = MockEngine::new("uci_stop", MOCK_ENGINE);
+    let mut engine = UciEngine::start(script.path()).unwrap();
+
+    engine.go(&GoLimits::Infinite).unwrap();
+    engine.stop().unwrap();
+
+    let events = wait_for_bestmove(|| engine.poll());
+
+    assert_eq!(
+        events.last(),
+        Some(&UciEvent::BestMove {
+            mv: Some("d2d4".to_owned()),
+            ponder: None,
+        })
+    );
+}
+
+#[test]
+fn missing_engine_fails_to_start() {
+    assert!(UciEngine::start("/nonexistent/uci/engine").is_err());
+}
+
+#[test]
+fn parses_engine_output() {
+    let info = uci::parse_info("info depth 7 seldepth 9 multipv 2 score cp -31 upperbound pv g8f6")
+        .unwrap();
+
+    assert_eq!(info.depth, Some(7));
+    assert_eq!(info.multipv, Some(2));
+    assert_eq!(info.score, Some(UciScore::Centipawns(-31)));
+    assert_eq!(info.pv, vec!["g8f6".to_owned()]);
+
+    assert_eq!(
+        uci::parse_bestmove("bestmove (none)"),
+        Some(UciEvent::BestMove {
+            mv: None,
+            ponder: None,
+        })
+    );
+    assert_eq!(uci::parse_info("readyok"), None);
+}
+
+#[test]
+fn mate_scores_match_the_built_in_engine() {
+    assert_eq!(
+        chess::engine::mate_in(UciScore::Mate(3).to_engine_score()),
+        Some(3)
+    );
+    assert_eq!(
+        chess::engine::mate_in(UciScore::Mate(-2).to_engine_score()),
+        Some(-2)
+    );
+}
+
+#[test]
+fn chess_uci_finds_mate_in_one() {
+    let mut engine = UciEngine::start(env!("CARGO_BIN_EXE_chess_uci")).unwrap();
+
+    assert!(engine.name().starts_with("Chess"));
+    assert_eq!(engine.author(), "Chess contributors");
+    assert_eq!(engine.options()[0].name, "Skill Level");
+    assert_eq!(engine.options()[1].name, "MultiPV");
+
+    engine.new_game().unwrap();
+    engine
+        .set_position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", &[])
+        .unwrap();
+    engine.go(&GoLimits::Depth(3)).unwrap();
+
+    let events = wait_for_bestmove(|| engine.poll());
+    let has_mate_score = events.iter().any(|event| match event {
+        UciEvent::Info(info) => info.score == Some(UciScore::Mate(1)),
+        _ => false,
+    });
+
+    assert!(has_mate_score);
+    assert_eq!(
+        events.last(),
+        Some(&UciEvent::BestMove {
+            mv: Some("a1a8".to_owned()),
+            ponder: None,
+        })
+    );
+}